@@ -1,4 +1,5 @@
-use super::Db;
+use super::{Db, DbResult, SimplifyDbResult};
+use amplitude_runner::variant::Variant;
 use anyhow::{bail, Result};
 use derive_more::Deref;
 use rand::seq::SliceRandom;
@@ -7,6 +8,15 @@ use rusqlite::params;
 #[derive(Deref)]
 pub struct MiscDb<'a>(pub(super) &'a Db);
 
+/// The last code a member of a class submitted for an exercise in a language
+#[derive(Debug)]
+pub struct ClassSubmission {
+    pub user_id: String,
+    pub name: String,
+    pub lang: String,
+    pub code: String,
+}
+
 impl<'a> MiscDb<'a> {
     pub fn create_class(&self, id: u64, name: &str) -> Result<()> {
         self.lock().execute(
@@ -51,6 +61,22 @@ impl<'a> MiscDb<'a> {
         bail!("No unique class id found")
     }
 
+    /// The secret called `name`, generated the first time it's asked for
+    pub fn secret(&self, name: &str) -> Result<u64> {
+        let this = self.lock();
+        this.execute(
+            include_str!("./sql/secrets/insert_secret.sql"),
+            params![name, rand::random::<i64>()],
+        )?;
+        let value = this.query_row(
+            include_str!("./sql/secrets/load_secret.sql"),
+            params![name],
+            |x| x.get::<_, i64>(0),
+        )?;
+
+        Ok(value as u64)
+    }
+
     pub fn save_problem_progress(&self, user_id: &str, problem_id: &str, code: &str) -> Result<()> {
         // todo: validate problem_id
 
@@ -73,7 +99,7 @@ impl<'a> MiscDb<'a> {
 
         Ok(code)
     }
-    
+
     /// Get the list of problems that the user has completed.
     pub fn get_completed_problems(&self, user_id: &str) -> Result<Vec<String>> {
         let lock = self.lock();
@@ -87,7 +113,7 @@ impl<'a> MiscDb<'a> {
 
         Ok(ids)
     }
-    
+
     /// Get the list of problems a user is still working on
     pub fn get_incomplete_problems(&self, user_id: &str) -> Result<Vec<String>> {
         let lock = self.lock();
@@ -101,5 +127,113 @@ impl<'a> MiscDb<'a> {
 
         Ok(ids)
     }
-    
+
+    /// Get a user's own test cases for an exercise, if they were generated
+    /// from the same `fingerprint`
+    pub fn load_variant(
+        &self,
+        user_id: &str,
+        exercise_id: &str,
+        fingerprint: &str,
+    ) -> Result<Option<Variant>> {
+        let tests = self
+            .lock()
+            .query_row(
+                include_str!("./sql/variants/load_variant.sql"),
+                params![user_id, exercise_id, fingerprint],
+                |x| x.get::<_, String>(0),
+            )
+            .simplify()?;
+
+        Ok(match tests {
+            DbResult::Ok(tests) => Some(serde_json::from_str(&tests)?),
+            DbResult::NotFound => None,
+        })
+    }
+
+    pub fn save_variant(
+        &self,
+        user_id: &str,
+        exercise_id: &str,
+        fingerprint: &str,
+        variant: &Variant,
+    ) -> Result<()> {
+        self.lock().execute(
+            include_str!("./sql/variants/upsert_variant.sql"),
+            params![
+                user_id,
+                exercise_id,
+                fingerprint,
+                serde_json::to_string(variant)?
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get the cached result of evaluating a submission, as json
+    pub fn load_evaluation(&self, key: &str) -> Result<Option<String>> {
+        let result = self
+            .lock()
+            .query_row(
+                include_str!("./sql/evaluations/load_evaluation.sql"),
+                params![key],
+                |x| x.get::<_, String>(0),
+            )
+            .simplify()?;
+
+        Ok(match result {
+            DbResult::Ok(result) => Some(result),
+            DbResult::NotFound => None,
+        })
+    }
+
+    pub fn save_evaluation(&self, key: &str, result: &str) -> Result<()> {
+        self.lock().execute(
+            include_str!("./sql/evaluations/upsert_evaluation.sql"),
+            params![key, result],
+        )?;
+
+        Ok(())
+    }
+
+    /// Keep the code a user submitted, replacing what they last submitted in
+    /// the same language
+    pub fn save_submission(
+        &self,
+        user_id: &str,
+        exercise_id: &str,
+        lang: &str,
+        code: &str,
+    ) -> Result<()> {
+        self.lock().execute(
+            include_str!("./sql/submissions/upsert_submission.sql"),
+            params![user_id, exercise_id, lang, code],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get what everyone in a class last submitted for an exercise
+    pub fn class_submissions(
+        &self,
+        class_id: u64,
+        exercise_id: &str,
+    ) -> Result<Vec<ClassSubmission>> {
+        let lock = self.lock();
+        let mut stmt = lock.prepare(include_str!("./sql/submissions/class_submissions.sql"))?;
+        let mut rows = stmt.query(params![class_id, exercise_id])?;
+
+        let mut submissions = Vec::new();
+        while let Some(row) = rows.next()? {
+            submissions.push(ClassSubmission {
+                user_id: row.get(0)?,
+                name: row.get(1)?,
+                lang: row.get(2)?,
+                code: row.get(3)?,
+            });
+        }
+
+        Ok(submissions)
+    }
 }
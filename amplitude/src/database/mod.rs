@@ -15,7 +15,7 @@ pub mod user;
 type SessionMeta = (String, u64, Option<String>);
 
 // Increment every time schema changes, even in dev
-const DATABASE_VERSION: u64 = 3;
+const DATABASE_VERSION: u64 = 6;
 
 pub struct Db {
     inner: Mutex<Option<Connection>>,
@@ -102,6 +102,14 @@ impl Db {
             // == Problems ==
             include_str!("./sql/problems/create_problems.sql"),
             include_str!("./sql/problems/create_solutions.sql"),
+            // == Variants ==
+            include_str!("./sql/variants/create_variants.sql"),
+            // == Evaluations ==
+            include_str!("./sql/evaluations/create_evaluations.sql"),
+            // == Submissions ==
+            include_str!("./sql/submissions/create_submissions.sql"),
+            // == Secrets ==
+            include_str!("./sql/secrets/create_secrets.sql"),
         ] {
             trans.execute(i, [])?;
         }
@@ -134,6 +142,11 @@ impl Db {
         ] {
             trans.execute(i, [cutoff])?;
         }
+        let cutoff = current_epoch() - 7 * 24 * 60 * 60; // (one week)
+        trans.execute(
+            include_str!("./sql/evaluations/delete_evaluations.sql"),
+            [cutoff],
+        )?;
         trans.commit()?;
 
         Ok(())
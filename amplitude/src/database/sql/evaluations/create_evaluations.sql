@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS evaluations (
+    key TEXT NOT NULL PRIMARY KEY, -- Hash of the exercise and submission
+    result TEXT NOT NULL,          -- What evaluating the submission returned, as json
+    created INTEGER NOT NULL       -- Time created in epoch
+)
\ No newline at end of file
@@ -0,0 +1,2 @@
+DELETE FROM evaluations
+WHERE created < ?;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT result FROM evaluations WHERE key = ?1;
\ No newline at end of file
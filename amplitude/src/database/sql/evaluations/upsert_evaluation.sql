@@ -0,0 +1,6 @@
+INSERT INTO evaluations (key, result, created)
+VALUES (?1, ?2, strftime('%s','now')) ON CONFLICT DO
+UPDATE
+SET
+    result = ?2,
+    created = strftime('%s','now')
\ No newline at end of file
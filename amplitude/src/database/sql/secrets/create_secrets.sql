@@ -0,0 +1,5 @@
+-- Random keys generated once, so things derived from them stay the same across restarts
+CREATE TABLE IF NOT EXISTS secrets (
+    name TEXT NOT NULL PRIMARY KEY,
+    value INTEGER NOT NULL
+)
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO secrets (name, value) VALUES (?1, ?2) ON CONFLICT DO NOTHING;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT value FROM secrets WHERE name = ?1;
\ No newline at end of file
@@ -0,0 +1,5 @@
+SELECT submissions.user_id, users.name, submissions.lang, submissions.code
+FROM submissions
+    JOIN class_members ON submissions.user_id = class_members.user_id
+    JOIN users ON submissions.user_id = users.id
+WHERE class_members.class_id = ?1 AND submissions.exercise_id = ?2;
\ No newline at end of file
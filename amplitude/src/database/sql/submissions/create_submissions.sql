@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS submissions (
+    user_id TEXT NOT NULL,
+    exercise_id TEXT NOT NULL,
+    lang TEXT NOT NULL,
+    code TEXT NOT NULL,      -- The whole source that was run
+    created INTEGER NOT NULL, -- Time last submitted in epoch
+    UNIQUE(user_id, exercise_id, lang)
+)
\ No newline at end of file
@@ -0,0 +1,12 @@
+INSERT INTO submissions (
+    user_id,
+    exercise_id,
+    lang,
+    code,
+    created
+    )
+VALUES (?1, ?2, ?3, ?4, strftime('%s','now')) ON CONFLICT DO
+UPDATE
+SET
+    code = ?4,
+    created = strftime('%s','now')
\ No newline at end of file
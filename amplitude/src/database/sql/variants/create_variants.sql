@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS variants (
+    user_id TEXT NOT NULL,
+    exercise_id TEXT NOT NULL,
+    fingerprint TEXT NOT NULL, -- What the test cases were generated from
+    tests TEXT NOT NULL,       -- Visible test cases by function, as json
+    UNIQUE(user_id, exercise_id)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT tests FROM variants WHERE user_id = ?1 AND exercise_id = ?2 AND fingerprint = ?3;
\ No newline at end of file
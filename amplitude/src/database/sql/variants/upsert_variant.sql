@@ -0,0 +1,11 @@
+INSERT INTO variants (
+    user_id,
+    exercise_id,
+    fingerprint,
+    tests
+    )
+VALUES (?1, ?2, ?3, ?4) ON CONFLICT DO
+UPDATE
+SET
+    fingerprint = ?3,
+    tests = ?4
\ No newline at end of file
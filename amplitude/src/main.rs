@@ -21,6 +21,7 @@ mod misc;
 mod routes;
 mod session;
 mod state;
+mod workers;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     trace::set_log_formatter(AfireLogger);
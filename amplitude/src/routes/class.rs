@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+
 use afire::{Method, Response, Server, Status};
+use amplitude_runner::{lang::Language, similarity::compare_all};
 use serde::Deserialize;
 use serde_json::json;
 
-use crate::error::HandledRoute;
+use crate::error::{HandledRoute, StatusContext};
 use crate::session::{assert_admin, get_session};
 use crate::State;
 
@@ -109,4 +112,69 @@ pub fn attach(server: &mut Server<State>) {
 
         Ok(Response::new().text(json!({})))
     });
+
+    // Allows an admin to find students in a class with suspiciously similar
+    // submissions to an exercise, comparing what each last submitted in the
+    // same language. Names, whitespace, comments and the template don't
+    // count. Lines are from 1, and pairs are most similar first.
+    // > POST /api/class/similarity
+    // > Cookie: <session>
+    // > { "class_id": 123, "exercise_id": "basics/fizzbuzz" }
+    // < { "pairs": [ { "lang": "python", "a": { "id": "abc", "name": "..." }, "b": { ... }, "score": 0.9, "matches": [ { "a": [1, 12], "b": [2, 14] } ] } ] }
+    server.handled_stateful_route(Method::POST, "/api/class/similarity", |app, req| {
+        let session = get_session(&app, req)?;
+        assert_admin(&session)?;
+
+        #[derive(Debug, Deserialize)]
+        struct SimilarityRequest {
+            class_id: u64,
+            exercise_id: String,
+        }
+
+        let SimilarityRequest {
+            class_id,
+            exercise_id,
+        } = json(req)?;
+        let parse_data = app.parse_data();
+        let e = parse_data
+            .exercises
+            .get(&exercise_id)
+            .with_context(Status::NotFound, || {
+                format!("Exercise `{exercise_id}` not found")
+            })?;
+
+        let mut by_lang = HashMap::<_, Vec<_>>::new();
+        for submission in app.db.misc().class_submissions(class_id, &exercise_id)? {
+            // languages that have since been removed can't be compared
+            if let Ok(lang) = submission.lang.parse::<Language>() {
+                by_lang.entry(lang).or_default().push(submission);
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for (lang, submissions) in &by_lang {
+            let template = e.lang_info.get(lang).map(|i| i.code.as_str());
+            let codes = submissions
+                .iter()
+                .map(|s| s.code.as_str())
+                .collect::<Vec<_>>();
+            for (a, b, similarity) in compare_all(lang, template.unwrap_or_default(), &codes) {
+                let (a, b) = (&submissions[a], &submissions[b]);
+                pairs.push((
+                    similarity.score,
+                    json!({
+                        "lang": lang,
+                        "a": { "id": a.user_id, "name": a.name },
+                        "b": { "id": b.user_id, "name": b.name },
+                        "score": similarity.score,
+                        "matches": similarity.matches,
+                    }),
+                ));
+            }
+        }
+        pairs.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        let pairs = pairs.into_iter().map(|(_, pair)| pair).collect::<Vec<_>>();
+
+        Ok(Response::new().text(json!({ "pairs": pairs })))
+    });
 }
@@ -1,19 +1,208 @@
-use amplitude_runner::lang::Language;
+use amplitude_runner::{
+    cache::{cache_key, exercise_version},
+    exercise::Submission,
+    lang::Language,
+};
 
-use super::*;
+use super::{exercise::for_user, *};
 
-use crate::{error::HandledRoute, state::State};
+use crate::{
+    error::{error, HandledRoute},
+    state::State,
+};
 
 #[derive(Debug, Deserialize)]
 struct EvaluateReq {
+    #[serde(default)]
     code: String,
+    /// The editable regions of the template, for templates that have them
+    #[serde(default)]
+    edits: Option<Vec<String>>,
+    /// Extra files submitted alongside `code`
+    #[serde(default)]
+    files: HashMap<String, String>,
     lang: Language,
     id: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct TryReq {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    edits: Option<Vec<String>>,
+    #[serde(default)]
+    files: HashMap<String, String>,
+    lang: Language,
+    id: String,
+    function: String,
+    inputs: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TraceReq {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    edits: Option<Vec<String>>,
+    #[serde(default)]
+    files: HashMap<String, String>,
+    lang: Language,
+    id: String,
+    function: String,
+    /// Which of the function's visible test cases to trace
+    test: usize,
+}
+
+fn submission<'a>(code: &'a str, edits: &'a Option<Vec<String>>) -> Submission<'a> {
+    match edits {
+        Some(edits) => Submission::Edits(edits),
+        None => Submission::Code(code),
+    }
+}
+
 pub fn attach(server: &mut Server<State>) {
     server.handled_stateful_route(Method::POST, "/api/evaluate", |state, req| {
         let body: EvaluateReq = json(req)?;
+        if !state.language_available(&body.lang) {
+            return error(
+                Status::ServiceUnavailable,
+                &format!("Language `{}` is currently unavailable", body.lang.name()),
+            );
+        }
+
+        let parse_data = state.parse_data();
+        let e = parse_data
+            .exercises
+            .get(&body.id)
+            .with_context(Status::NotFound, || {
+                format!("Exercise `{}` not found", body.id)
+            })?;
+        let e = for_user(&state, req, &body.id, e)?;
+
+        let id = body.id.split_once('/').unwrap().1;
+        let submission = submission(&body.code, &body.edits);
+        let source = e
+            .source(&body.lang, &submission)
+            .context(Status::BadRequest, "Invalid submission")?;
+        e.check_files(&body.lang, &body.files, id, &state.config)
+            .context(Status::BadRequest, "Invalid files")?;
+
+        // kept for comparing the submissions of a class
+        if let Ok(session) = get_session(&state, req) {
+            state
+                .db
+                .misc()
+                .save_submission(&session.id, &body.id, body.lang.name(), &source)
+                .context(Status::InternalServerError, "Error saving submission")?;
+        }
+
+        // Unchanged resubmissions get the results from last time, unless some
+        // of them are timings, which change from run to run
+        let timed = e.config.functions.values().any(|f| f.performance.is_some());
+        let key = (!timed).then(|| {
+            cache_key(
+                &body.id,
+                &exercise_version(&e, &body.lang),
+                &body.lang,
+                &source,
+                &body.files,
+            )
+        });
+        if let Some(key) = &key {
+            let mut cached = state.cache.get(key);
+            if cached.is_none() && state.config.cache.persist {
+                cached = state
+                    .db
+                    .misc()
+                    .load_evaluation(key)
+                    .context(Status::InternalServerError, "Error loading cached results")?;
+            }
+            if let Some(results) = cached {
+                return Ok(Response::new()
+                    .header("Content-Type", "application/json")
+                    .text(results));
+            }
+        }
+
+        let results = if e.config.mutation.is_some() {
+            let results = e
+                .run_mutation(&body.lang, &submission, &body.files, id, &state.config)
+                .context(Status::InternalServerError, "Error running tests")?;
+            serde_json::to_string(&results)?
+        } else {
+            let results = e
+                .run_tests(&body.lang, &submission, &body.files, id, &state.config)
+                .context(Status::InternalServerError, "Error running tests")?;
+            serde_json::to_string(&results)?
+        };
+
+        if let Some(key) = key {
+            if state.config.cache.persist {
+                state
+                    .db
+                    .misc()
+                    .save_evaluation(&key, &results)
+                    .context(Status::InternalServerError, "Error caching results")?;
+            }
+            state.cache.insert(key, results.clone());
+        }
+
+        Ok(Response::new()
+            .header("Content-Type", "application/json")
+            .text(results))
+    });
+
+    // Calls one function with custom inputs, alongside the reference solution
+    // > POST /api/exercise/try
+    // > { "lang": "python", "code": "...", "id": "...", "function": "...", "inputs": [...] }
+    // < { "output": { "type": "answer", ... }, "expected": { "type": "answer", ... } }
+    server.handled_stateful_route(Method::POST, "/api/exercise/try", |state, req| {
+        let body: TryReq = json(req)?;
+        if !state.language_available(&body.lang) {
+            return error(
+                Status::ServiceUnavailable,
+                &format!("Language `{}` is currently unavailable", body.lang.name()),
+            );
+        }
+
+        let parse_data = state.parse_data();
+        let e = parse_data
+            .exercises
+            .get(&body.id)
+            .with_context(Status::NotFound, || {
+                format!("Exercise `{}` not found", body.id)
+            })?;
+
+        let id = body.id.split_once('/').unwrap().1;
+        let result = e
+            .try_call(
+                &body.lang,
+                &submission(&body.code, &body.edits),
+                &body.files,
+                &body.function,
+                body.inputs,
+                id,
+                &state.config,
+            )
+            .context(Status::BadRequest, "Error running function")?;
+
+        Ok(Response::new().json(result)?)
+    });
+
+    // Records every line run while calling one function with one of its visible
+    // test cases, for stepping through
+    // > POST /api/exercise/trace
+    // > { "lang": "python", "code": "...", "id": "...", "function": "...", "test": 0 }
+    // < { "trace": { "events": [{ "line": 1, "stack": [...], "locals": {...}, "stdout": "..." }], "truncated": false }, "output": { "type": "answer", ... }, "expected": ... }
+    server.handled_stateful_route(Method::POST, "/api/exercise/trace", |state, req| {
+        let body: TraceReq = json(req)?;
+        if !state.language_available(&body.lang) {
+            return error(
+                Status::ServiceUnavailable,
+                &format!("Language `{}` is currently unavailable", body.lang.name()),
+            );
+        }
 
         let parse_data = state.parse_data();
         let e = parse_data
@@ -22,12 +211,21 @@ pub fn attach(server: &mut Server<State>) {
             .with_context(Status::NotFound, || {
                 format!("Exercise `{}` not found", body.id)
             })?;
+        let e = for_user(&state, req, &body.id, e)?;
 
         let id = body.id.split_once('/').unwrap().1;
-        let results = e
-            .run_tests(&body.lang, &body.code, id, &state.config)
-            .context(Status::InternalServerError, "Error running tests")?;
+        let result = e
+            .trace(
+                &body.lang,
+                &submission(&body.code, &body.edits),
+                &body.files,
+                &body.function,
+                body.test,
+                id,
+                &state.config,
+            )
+            .context(Status::BadRequest, "Error tracing function")?;
 
-        Ok(Response::new().json(results)?)
+        Ok(Response::new().json(result)?)
     });
 }
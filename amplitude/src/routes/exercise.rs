@@ -1,6 +1,10 @@
+use std::borrow::Cow;
+
 use amplitude_markdown::items::exercise::transform;
+use amplitude_runner::{exercise::Exercise, variant::fingerprint};
 
 use super::*;
+use crate::error::StatusError;
 
 pub fn attach(server: &mut Server<State>) {
     server.handled_stateful_route(Method::POST, "/api/exercise", |state, req| {
@@ -9,19 +13,22 @@ pub fn attach(server: &mut Server<State>) {
             id: String,
         }
 
-        let mut req: ExerciseReq = json(req)?;
-        req.id = req.id.strip_prefix('/').unwrap_or(&req.id).to_string();
+        let mut body: ExerciseReq = json(req)?;
+        body.id = body.id.strip_prefix('/').unwrap_or(&body.id).to_string();
 
         let parse_data = state.parse_data();
         let e = parse_data
             .exercises
-            .get(&req.id)
+            .get(&body.id)
             .with_context(Status::NotFound, || {
-                format!("Exercise `{}` not found", req.id)
+                format!("Exercise `{}` not found", body.id)
             })?;
 
-        let mut clone = e.clone();
+        let mut clone = for_user(&state, req, &body.id, e)?.into_owned();
         transform(&mut clone);
+        for (lang, info) in clone.lang_info.iter_mut() {
+            info.available = state.language_available(lang);
+        }
 
         Ok(Response::new().json(clone)?)
     });
@@ -50,3 +57,38 @@ pub fn attach(server: &mut Server<State>) {
         Ok(Response::new().json(exercises)?)
     })
 }
+
+/// `e` with the signed in user's own visible test cases, when it has functions
+/// with variants. Anyone not signed in gets the shared ones.
+pub fn for_user<'a>(
+    state: &State,
+    req: &Request,
+    id: &str,
+    e: &'a Exercise,
+) -> Result<Cow<'a, Exercise>, StatusError> {
+    if !e.has_variants() {
+        return Ok(Cow::Borrowed(e));
+    }
+    let Ok(session) = get_session(state, req) else {
+        return Ok(Cow::Borrowed(e));
+    };
+
+    let db = state.db.misc();
+    let fingerprint = fingerprint(e);
+    let variant = match db.load_variant(&session.id, id, &fingerprint)? {
+        Some(variant) => variant,
+        None => {
+            let variant = e
+                .generate_variant(id, &session.id, &state.config)
+                .context(Status::InternalServerError, "Error generating test cases")?;
+            db.save_variant(&session.id, id, &fingerprint, &variant)?;
+            variant
+        }
+    };
+
+    let code_file = id.split_once('/').unwrap().1;
+    let e = e
+        .with_variant(variant, code_file)
+        .context(Status::InternalServerError, "Error loading test cases")?;
+    Ok(Cow::Owned(e))
+}
@@ -0,0 +1,55 @@
+use amplitude_runner::{exercise::MAX_FILE_SIZE, formatter::format_code, lang::Language};
+
+use super::*;
+
+use crate::error::error;
+
+#[derive(Debug, Deserialize)]
+struct FormatReq {
+    lang: Language,
+    code: String,
+}
+
+pub fn attach(server: &mut Server<State>) {
+    // Formats some code with the language's formatter
+    // > POST /api/format
+    // > Cookie: <session>
+    // > { "lang": "python", "code": "..." }
+    // < { "type": "code", "code": "..." } or { "type": "error", "message": "..." }
+    server.handled_stateful_route(Method::POST, "/api/format", |state, req| {
+        get_session(&state, req)?;
+
+        let body: FormatReq = json(req)?;
+        if !state.language_available(&body.lang) {
+            return error(
+                Status::ServiceUnavailable,
+                &format!("Language `{}` is currently unavailable", body.lang.name()),
+            );
+        }
+        if body.code.len() > MAX_FILE_SIZE {
+            return error(
+                Status::BadRequest,
+                &format!("Code must be at most {MAX_FILE_SIZE} bytes"),
+            );
+        }
+
+        let lang = state
+            .config
+            .docker
+            .language_config
+            .get(body.lang.name())
+            .with_context(Status::InternalServerError, || {
+                format!("Language `{}` not configured", body.lang.name())
+            })?;
+        if lang.formatter.is_none() {
+            return error(
+                Status::BadRequest,
+                &format!("Language `{}` has no formatter", body.lang.name()),
+            );
+        }
+        let formatted = format_code(lang, &state.config.docker, &body.code)
+            .context(Status::InternalServerError, "Error formatting code")?;
+
+        Ok(Response::new().json(formatted)?)
+    });
+}
@@ -0,0 +1,33 @@
+use amplitude_runner::lang::Language;
+
+use super::*;
+
+#[derive(Debug, Serialize)]
+struct LanguageRes {
+    name: Language,
+    display_name: &'static str,
+    extension: &'static str,
+    available: bool,
+    /// Whether code can be formatted with `/api/format`
+    formatter: bool,
+}
+
+pub fn attach(server: &mut Server<State>) {
+    // Lists every configured language
+    // > GET /api/languages
+    // < [{ "name": "python", "display_name": "Python", "extension": "py", "available": true, "formatter": true }, ...]
+    server.handled_stateful_route(Method::GET, "/api/languages", |state, _req| {
+        let mut languages = Language::all()
+            .map(|lang| LanguageRes {
+                name: lang,
+                display_name: lang.display_name(),
+                extension: lang.extension(),
+                available: state.language_available(&lang),
+                formatter: lang.config().formatter.is_some(),
+            })
+            .collect::<Vec<_>>();
+        languages.sort_by_key(|lang| lang.display_name);
+
+        Ok(Response::new().json(languages)?)
+    });
+}
@@ -15,8 +15,12 @@ mod category;
 mod class;
 mod evaluate;
 mod exercise;
+mod format;
+mod languages;
 mod list;
+mod parsons;
 mod problem;
+mod run;
 
 pub fn attach(server: &mut Server<State>) {
     server.route(Method::ANY, "/api/**", |_| {
@@ -31,7 +35,11 @@ pub fn attach(server: &mut Server<State>) {
     evaluate::attach(server);
     list::attach(server);
     exercise::attach(server);
+    format::attach(server);
+    languages::attach(server);
+    parsons::attach(server);
     problem::attach(server);
+    run::attach(server);
 }
 
 pub fn json<T>(req: &Request) -> anyhow::Result<T>
@@ -0,0 +1,72 @@
+use amplitude_markdown::items::parsons::Grading;
+
+use super::*;
+
+use crate::error::error;
+
+#[derive(Debug, Deserialize)]
+struct ParsonsReq {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EvaluateReq {
+    id: String,
+    /// The ids of the lines the student picked, in order
+    lines: Vec<String>,
+}
+
+pub fn attach(server: &mut Server<State>) {
+    // > POST /api/parsons
+    // > { "id": "<category>/<parsons>" }
+    // < { "title": "...", "instructions": "...", "lang": "python", "lines": [{ "id": "...", "code": "..." }] }
+    server.handled_stateful_route(Method::POST, "/api/parsons", |state, req| {
+        let mut req: ParsonsReq = json(req)?;
+        req.id = req.id.strip_prefix('/').unwrap_or(&req.id).to_string();
+
+        let parse_data = state.parse_data();
+        let parsons = parse_data
+            .parsons
+            .get(&req.id)
+            .with_context(Status::NotFound, || {
+                format!("Parsons problem `{}` not found", req.id)
+            })?;
+
+        Ok(Response::new().json(parsons)?)
+    });
+
+    // > POST /api/parsons/evaluate
+    // > { "id": "<category>/<parsons>", "lines": ["<line id>", ...] }
+    // < { "passed": true, "results": { ... } }
+    server.handled_stateful_route(Method::POST, "/api/parsons/evaluate", |state, req| {
+        let mut body: EvaluateReq = json(req)?;
+        body.id = body.id.strip_prefix('/').unwrap_or(&body.id).to_string();
+
+        let parse_data = state.parse_data();
+        let parsons = parse_data
+            .parsons
+            .get(&body.id)
+            .with_context(Status::NotFound, || {
+                format!("Parsons problem `{}` not found", body.id)
+            })?;
+        if parsons.grading == Grading::Run && !state.language_available(&parsons.lang) {
+            return error(
+                Status::ServiceUnavailable,
+                &format!(
+                    "Language `{}` is currently unavailable",
+                    parsons.lang.name()
+                ),
+            );
+        }
+
+        parsons
+            .code(&body.lines)
+            .context(Status::BadRequest, "Invalid lines")?;
+        let id = body.id.split_once('/').unwrap().1;
+        let result = parsons
+            .evaluate(&body.lines, id, &state.config)
+            .context(Status::InternalServerError, "Error checking lines")?;
+
+        Ok(Response::new().json(result)?)
+    });
+}
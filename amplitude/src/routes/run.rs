@@ -0,0 +1,83 @@
+use amplitude_runner::{
+    diagnostics::{CompileError, Diagnostic},
+    exercise::MAX_FILE_SIZE,
+    lang::Language,
+    runner::{run, RunOptions, RunOutput},
+};
+
+use super::*;
+
+use crate::error::error;
+
+#[derive(Debug, Deserialize)]
+struct RunReq {
+    lang: Language,
+    code: String,
+    stdin: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RunRes {
+    #[serde(flatten)]
+    output: RunOutput,
+    /// Where the compiler's errors are, for code that didn't compile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diagnostics: Option<Vec<Diagnostic>>,
+}
+
+pub fn attach(server: &mut Server<State>) {
+    // Runs some code without grading it
+    // > POST /api/run
+    // > Cookie: <session>
+    // > { "lang": "python", "code": "...", "stdin": "..." }
+    // < { "stdout": "...", "stderr": "...", "runtime": { ... }, "exit_code": 0, "truncated": false, "compile_error": false, "diagnostics": [...] }
+    server.handled_stateful_route(Method::POST, "/api/run", |state, req| {
+        get_session(&state, req)?;
+
+        let body: RunReq = json(req)?;
+        if !state.language_available(&body.lang) {
+            return error(
+                Status::ServiceUnavailable,
+                &format!("Language `{}` is currently unavailable", body.lang.name()),
+            );
+        }
+        if body.code.len() > MAX_FILE_SIZE
+            || body.stdin.as_ref().map_or(0, |s| s.len()) > MAX_FILE_SIZE
+        {
+            return error(
+                Status::BadRequest,
+                &format!("Code and stdin must be at most {MAX_FILE_SIZE} bytes"),
+            );
+        }
+
+        let lang = state
+            .config
+            .docker
+            .language_config
+            .get(body.lang.name())
+            .with_context(Status::InternalServerError, || {
+                format!("Language `{}` not configured", body.lang.name())
+            })?;
+        let opts = RunOptions {
+            stdin: body.stdin.as_deref(),
+            ..Default::default()
+        };
+        let output = run(
+            lang,
+            &state.config.docker,
+            &body.code,
+            HashMap::new(),
+            &opts,
+        )
+        .context(Status::InternalServerError, "Error running code")?;
+        let diagnostics = output.compile_error.then(|| {
+            CompileError::parse(lang.diagnostics, &output.stderr, &lang.source_path, &[])
+                .diagnostics
+        });
+
+        Ok(Response::new().json(RunRes {
+            output,
+            diagnostics,
+        })?)
+    });
+}
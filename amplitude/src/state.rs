@@ -1,15 +1,19 @@
-use std::{fs, path::PathBuf};
+use std::{collections::HashSet, fs, path::PathBuf};
 
 use amplitude_common::{
     config::{Args, AuthConfig, Config},
     default,
 };
+use amplitude_runner::{
+    cache::EvaluationCache, health::check_languages, images::ImageStatus, lang::Language,
+    runner::set_sandbox,
+};
 use anyhow::Context;
 use parking_lot::{RwLock, RwLockReadGuard};
 use rusqlite::Connection;
-use tracing::warn;
+use tracing::{info, warn};
 
-use crate::database::Db;
+use crate::{database::Db, workers::Workers};
 
 use amplitude_markdown::parse::{parse, ParseData};
 
@@ -17,6 +21,10 @@ pub struct State {
     pub db: Db,
     pub parse_data: RwLock<ParseData>,
     pub config: Config,
+    /// The languages that passed the health check on startup
+    pub available_languages: HashSet<String>,
+    /// The results of recent evaluations, as json
+    pub cache: EvaluationCache,
 }
 
 impl State {
@@ -24,6 +32,11 @@ impl State {
         self.parse_data.read()
     }
 
+    /// Whether code in `lang` can currently be run
+    pub fn language_available(&self, lang: &Language) -> bool {
+        self.available_languages.contains(lang.name())
+    }
+
     pub fn new() -> anyhow::Result<Self> {
         let args = Args::parse();
         let mut config: Config =
@@ -44,6 +57,7 @@ impl State {
 
         config.args = args;
         config.auth = auth;
+        config.docker.load_languages()?;
 
         let tmp_folder = PathBuf::from(&config.docker.tmp_folder);
         if !tmp_folder.exists() {
@@ -56,13 +70,51 @@ impl State {
                 .context("While opening connection to Database")?,
         );
         db.init().context("While initializing Database")?;
+        config.parse.secret = db.misc().secret("parsons")?;
+
+        info!("Checking languages...");
+        let available_languages = match config.workers.urls.is_empty() {
+            true => check_local_languages(&config),
+            false => {
+                info!("Running code on {} worker(s)", config.workers.urls.len());
+                let workers = Workers::new(&config.workers);
+                let languages = workers.languages();
+                set_sandbox(Box::new(workers))?;
+                languages
+            }
+        };
+        let mut available = available_languages.iter().collect::<Vec<_>>();
+        available.sort();
+        info!("Available languages: {available:?}");
 
         let parse_data = parse(&config)?;
 
         Ok(Self {
             db,
             parse_data: RwLock::new(parse_data),
+            cache: EvaluationCache::new(config.cache.entries),
             config,
+            available_languages,
         })
     }
 }
+
+/// The languages that can be run with docker on this machine
+fn check_local_languages(config: &Config) -> HashSet<String> {
+    let mut available_languages = HashSet::new();
+    for (lang, result) in check_languages(&config.docker) {
+        match result {
+            Ok(status) => {
+                if status == ImageStatus::Outdated {
+                    warn!(
+                        "Image for `{lang}` is outdated, try running `cargo r -p amplitude_runner \
+                         -- build {lang}`"
+                    );
+                }
+                available_languages.insert(lang);
+            }
+            Err(e) => warn!("Language `{lang}` is unavailable: {e:#}"),
+        }
+    }
+    available_languages
+}
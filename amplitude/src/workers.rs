@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+use amplitude_common::config::{LanguageConfig, WorkersConfig};
+use amplitude_runner::{
+    runner::{RunOptions, RunOutput, Sandbox},
+    worker::{Health, Job},
+};
+use anyhow::Context;
+use tracing::{trace, warn};
+
+/// Runs code on the workers in the config, sending each job to whichever is
+/// waited on for the fewest jobs, and on to the next when one is full, fails
+/// or can't be reached
+pub struct Workers {
+    workers: Vec<Worker>,
+    token: Option<String>,
+    retries: u32,
+    agent: ureq::Agent,
+    /// Where to start looking for the least busy worker, so ties take turns
+    next: AtomicUsize,
+}
+
+struct Worker {
+    url: String,
+    /// How many jobs sent to the worker haven't finished
+    running: AtomicUsize,
+}
+
+impl Workers {
+    pub fn new(cfg: &WorkersConfig) -> Self {
+        Self {
+            workers: cfg
+                .urls
+                .iter()
+                .map(|url| Worker {
+                    url: url.trim_end_matches('/').to_string(),
+                    running: AtomicUsize::new(0),
+                })
+                .collect(),
+            token: cfg.token.clone(),
+            retries: cfg.retries,
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(cfg.timeout))
+                .build(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn authorize(&self, req: ureq::Request) -> ureq::Request {
+        match &self.token {
+            Some(token) => req.set("Authorization", &format!("Bearer {token}")),
+            None => req,
+        }
+    }
+
+    /// The languages at least one worker can run, leaving out workers that
+    /// can't be reached
+    pub fn languages(&self) -> HashSet<String> {
+        let mut languages = HashSet::new();
+        for worker in &self.workers {
+            let health = self
+                .authorize(self.agent.get(&format!("{}/health", worker.url)))
+                .call()
+                .map_err(anyhow::Error::from)
+                .and_then(|res| Ok(res.into_json::<Health>()?));
+            match health {
+                Ok(health) => languages.extend(health.languages),
+                Err(e) => warn!("Worker `{}` is unavailable: {e:#}", worker.url),
+            }
+        }
+        languages
+    }
+
+    /// The workers to try a job on, least busy first
+    fn order(&self) -> Vec<&Worker> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut order = (0..self.workers.len())
+            .map(|i| &self.workers[(start + i) % self.workers.len()])
+            .collect::<Vec<_>>();
+        order.sort_by_key(|w| w.running.load(Ordering::SeqCst));
+        order
+    }
+}
+
+impl Sandbox for Workers {
+    fn run(
+        &self,
+        lang: &LanguageConfig,
+        src: &str,
+        other_files: HashMap<String, &[u8]>,
+        opts: &RunOptions,
+    ) -> anyhow::Result<RunOutput> {
+        let job = Job::new(&lang.name, src, &other_files, opts);
+
+        let order = self.order();
+        let mut errors = Vec::new();
+        for attempt in 0..=self.retries as usize {
+            let worker = order[attempt % order.len()];
+            if attempt >= order.len() {
+                // every worker has been tried, so give them a moment
+                thread::sleep(Duration::from_millis(100 * attempt as u64));
+            }
+
+            worker.running.fetch_add(1, Ordering::SeqCst);
+            let res = self
+                .authorize(self.agent.post(&format!("{}/run", worker.url)))
+                .send_json(&job);
+            worker.running.fetch_sub(1, Ordering::SeqCst);
+
+            match res {
+                Ok(res) => {
+                    return res.into_json::<RunOutput>().with_context(|| {
+                        format!("While reading output from worker `{}`", worker.url)
+                    })
+                }
+                // full, can't run the language, or broken
+                Err(ureq::Error::Status(status @ 500.., res)) => {
+                    let body = res.into_string().unwrap_or_default();
+                    trace!(
+                        "Worker `{}` turned away job with {status}: {body}",
+                        worker.url
+                    );
+                    errors.push(format!("{} ({status}): {body}", worker.url));
+                }
+                Err(ureq::Error::Status(status, res)) => anyhow::bail!(
+                    "Worker `{}` responded with {status}: {}",
+                    worker.url,
+                    res.into_string().unwrap_or_default()
+                ),
+                Err(e) => {
+                    warn!("Worker `{}` is unavailable: {e}", worker.url);
+                    errors.push(format!("{}: {e}", worker.url));
+                }
+            }
+        }
+
+        anyhow::bail!("No worker could run the job:\n{}", errors.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::Arc,
+    };
+
+    use super::*;
+
+    /// A worker that answers every request with `status` and `body`, counting
+    /// the requests it gets
+    fn stub(status: u16, body: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut len = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            len = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                reader.read_exact(&mut vec![0; len]).unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        (url, hits)
+    }
+
+    const OUTPUT: &str = r#"{"stdout":"hi","stderr":"","runtime":{"secs":0,"nanos":0},"exit_code":0,"truncated":false}"#;
+
+    fn workers(urls: Vec<String>, retries: u32) -> Workers {
+        Workers::new(&WorkersConfig {
+            urls,
+            token: None,
+            retries,
+            timeout: 5,
+        })
+    }
+
+    fn run(workers: &Workers) -> anyhow::Result<RunOutput> {
+        let lang: LanguageConfig = toml::from_str(
+            "display_name = 'Python'\nimage_name = 'python'\nsource_path = 'main.py'\n\
+             extension = 'py'\ndelimiter = '#'\n",
+        )
+        .unwrap();
+        workers.run(&lang, "print('hi')", HashMap::new(), &RunOptions::default())
+    }
+
+    #[test]
+    fn test_order() {
+        let workers = workers(vec!["a".into(), "b".into(), "c".into()], 0);
+        let urls = |order: Vec<&Worker>| order.iter().map(|w| w.url.clone()).collect::<Vec<_>>();
+
+        // ties take turns
+        assert_eq!(urls(workers.order()), ["a", "b", "c"]);
+        assert_eq!(urls(workers.order()), ["b", "c", "a"]);
+
+        // the least busy go first
+        workers.workers[0].running.store(2, Ordering::SeqCst);
+        workers.workers[2].running.store(1, Ordering::SeqCst);
+        assert_eq!(urls(workers.order()), ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_unavailable_moves_on() {
+        let (full, full_hits) = stub(503, "No free slots");
+        let (free, free_hits) = stub(200, OUTPUT);
+        let workers = workers(vec![full, free], 2);
+
+        let output = run(&workers).unwrap();
+        assert_eq!(output.stdout, "hi");
+        assert_eq!(full_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(free_hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_client_error_bails() {
+        let (bad, bad_hits) = stub(400, "Invalid job");
+        let (free, free_hits) = stub(200, OUTPUT);
+        let workers = workers(vec![bad, free], 2);
+
+        let err = run(&workers).unwrap_err().to_string();
+        assert!(err.contains("400"), "{err}");
+        assert!(err.contains("Invalid job"), "{err}");
+        assert_eq!(bad_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(free_hits.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_retries() {
+        let (full, hits) = stub(503, "No free slots");
+        let workers = workers(vec![full], 2);
+
+        let err = run(&workers).unwrap_err().to_string();
+        assert!(err.contains("No worker could run the job"), "{err}");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+}
@@ -2,8 +2,10 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     path::PathBuf,
+    sync::OnceLock,
 };
 
+use anyhow::Context;
 use clap::Parser;
 use serde::Deserialize;
 
@@ -37,6 +39,10 @@ pub struct ParseConfig {
     pub asset_path: String,
     pub asset_prefix: String,
     pub image_extensions: HashSet<String>,
+    /// Mixed into the ids of parsons lines. Set from the database, so the ids
+    /// stay the same across restarts.
+    #[serde(skip)]
+    pub secret: u64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -69,17 +75,128 @@ pub struct Config {
     #[serde(default)]
     pub auth: AuthConfig,
     pub parse: ParseConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub workers: WorkersConfig,
     #[serde(default = "args")]
     pub args: Args,
 }
 
-fn language_config() -> HashMap<String, LanguageConfig> {
-    toml::from_str(
-        fs::read_to_string(path::LANGUAGES.join("languages.toml"))
-            .unwrap()
-            .as_str(),
+/// Where the results of evaluating submissions are kept, so resubmitting the
+/// same code doesn't run it again
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    /// How many results are kept in memory, 0 turning the cache off
+    #[serde(default = "cache_entries")]
+    pub entries: usize,
+    /// Whether results are also kept in the database, so they outlive restarts
+    /// and entries pushed out of memory
+    #[serde(default)]
+    pub persist: bool,
+}
+
+fn cache_entries() -> usize {
+    1024
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            entries: cache_entries(),
+            persist: false,
+        }
+    }
+}
+
+/// Machines running the runner's `worker` binary. Code is run on them instead
+/// of with docker on this machine when there are any.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WorkersConfig {
+    /// The url of each worker, like `http://localhost:8081`
+    #[serde(default)]
+    pub urls: Vec<String>,
+    /// Sent with every request to a worker, which is required by workers when
+    /// set
+    #[serde(default)]
+    pub token: Option<String>,
+    /// How many more times a job is sent when a worker is full, fails or can't
+    /// be reached, going to the next worker each time
+    #[serde(default = "worker_retries")]
+    pub retries: u32,
+    /// How many seconds to wait for a worker to respond
+    #[serde(default = "worker_timeout")]
+    pub timeout: u64,
+}
+
+fn worker_retries() -> u32 {
+    2
+}
+
+fn worker_timeout() -> u64 {
+    60
+}
+
+impl Default for WorkersConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            token: None,
+            retries: worker_retries(),
+            timeout: worker_timeout(),
+        }
+    }
+}
+
+fn load_languages() -> anyhow::Result<HashMap<String, LanguageConfig>> {
+    let path = path::LANGUAGES.join("languages.toml");
+    let mut languages: HashMap<String, LanguageConfig> = toml::from_str(
+        &fs::read_to_string(&path)
+            .with_context(|| format!("While reading `{}`", path.display()))?,
     )
-    .unwrap()
+    .with_context(|| format!("While parsing `{}`", path.display()))?;
+
+    let mut idents = HashSet::new();
+    for (name, lang) in &mut languages {
+        lang.name = name.clone();
+        anyhow::ensure!(
+            path::LANGUAGES.join(name).is_dir(),
+            "Expected a `{}` directory for language `{name}`",
+            path::LANGUAGES.join(name).display()
+        );
+        for ident in lang.idents(name).collect::<HashSet<_>>() {
+            anyhow::ensure!(
+                idents.insert(ident.clone()),
+                "`{ident}` refers to more than one language"
+            );
+        }
+    }
+    Ok(languages)
+}
+
+static LANGUAGES: OnceLock<HashMap<String, LanguageConfig>> = OnceLock::new();
+
+/// The languages loaded by `DockerConfig::load_languages`, by name, which
+/// every `DockerConfig` and `Language` shares. Empty until then.
+pub fn languages() -> &'static HashMap<String, LanguageConfig> {
+    static NONE: OnceLock<HashMap<String, LanguageConfig>> = OnceLock::new();
+    LANGUAGES
+        .get()
+        .unwrap_or_else(|| NONE.get_or_init(HashMap::new))
+}
+
+fn output_limit() -> usize {
+    64 * 1024
+}
+
+fn tmpfs_size() -> String {
+    "64m".to_string()
+}
+
+fn seccomp() -> PathBuf {
+    path::LANGUAGES.join("seccomp.json")
 }
 
 #[derive(Deserialize, Debug)]
@@ -88,8 +205,33 @@ pub struct DockerConfig {
     pub tmp_folder: String,
     pub command: String,
     pub timeout: u64,
-    #[serde(default = "language_config")]
-    pub language_config: HashMap<String, LanguageConfig>,
+    /// The most bytes of stdout / stderr to keep before killing the container
+    #[serde(default = "output_limit")]
+    pub output_limit: usize,
+    /// The size of the writable tmpfs mounted at `/runner` and `/tmp`
+    #[serde(default = "tmpfs_size")]
+    pub tmpfs_size: String,
+    /// The seccomp profile to run containers with
+    #[serde(default = "seccomp")]
+    pub seccomp: PathBuf,
+    /// Every language in `languages.toml`, filled in by `load_languages`
+    #[serde(skip, default = "languages")]
+    pub language_config: &'static HashMap<String, LanguageConfig>,
+}
+
+impl DockerConfig {
+    /// Load `languages/languages.toml` into `language_config`, so the current
+    /// directory has to be set by then. They're only read the first time.
+    pub fn load_languages(&mut self) -> anyhow::Result<()> {
+        self.language_config = match LANGUAGES.get() {
+            Some(languages) => languages,
+            None => {
+                let loaded = load_languages().context("While loading languages")?;
+                LANGUAGES.get_or_init(|| loaded)
+            }
+        };
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -114,11 +256,76 @@ pub struct GithubBotAuth {
     pub installation_id: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct LanguageConfig {
+    /// The language's key in `languages.toml`, which is also the name of its
+    /// directory in `languages/`
+    #[serde(skip)]
+    pub name: String,
+    /// The name shown to users
+    pub display_name: String,
+    /// Other names the language can be referred to by
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub image_name: String,
     pub source_path: String,
     pub extension: String,
     pub delimiter: String,
+    /// Whether exercises in the language are run with `runner.hbs`, which SQL
+    /// and shell exercises are checked without
+    #[serde(default = "runner_template")]
+    pub runner_template: bool,
+    /// Whether the runner template can record an execution trace
+    #[serde(default)]
+    pub trace: bool,
+    /// Whether the runner template can collect line coverage
+    #[serde(default)]
+    pub coverage: bool,
+    /// How the compiler prints errors, for compiled languages
+    #[serde(default)]
+    pub diagnostics: Option<DiagnosticFormat>,
+    /// A linter installed in the language's image
+    #[serde(default)]
+    pub lint: Option<LintConfig>,
+    /// Prints the code at `source_path` formatted, run with bash in place of
+    /// `run.sh`
+    #[serde(default)]
+    pub formatter: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    /// Run with bash in place of `run.sh`, next to the code at `source_path`
+    pub command: String,
+    /// How the linter prints what it finds
+    pub format: DiagnosticFormat,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticFormat {
+    /// `file:line:column: severity: message`, from gcc and clang
+    Gcc,
+    /// Cargo's json messages
+    Rustc,
+    /// `file:line: severity: message`, with a caret under the column
+    Javac,
+    /// `file:line[:column]: message`, every message being a warning
+    Pyflakes,
+}
+
+fn runner_template() -> bool {
+    true
+}
+
+impl LanguageConfig {
+    /// Everything the language called `name` can be identified by, lowercase
+    pub fn idents<'a>(&'a self, name: &'a str) -> impl Iterator<Item = String> + 'a {
+        [name, &self.extension]
+            .into_iter()
+            .chain(self.aliases.iter().map(|a| a.as_str()))
+            .map(|s| s.to_ascii_lowercase())
+    }
 }
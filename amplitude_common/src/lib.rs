@@ -20,6 +20,7 @@ pub fn config_and_set_path() -> anyhow::Result<config::Config> {
     let mut config: Config = toml::from_str::<Config>(&fs::read_to_string(&args.config)?)
         .expect("Not a valid config file");
     config.args = args;
+    config.docker.load_languages()?;
 
     Ok(config)
 }
@@ -3,10 +3,98 @@ use super::*;
 use crate::parse::parse_md;
 pub use amplitude_runner::exercise::Exercise;
 use amplitude_runner::{
-    exercise::{generate, runner_template, ExerciseConfig, LanguageInfo},
+    constraint::generate_cases,
+    exercise::{
+        generate, runner_template, solve, split_regions, ExerciseConfig, LanguageInfo, Solution,
+        MAX_FILES, MAX_FILE_SIZE, MAX_FIXTURES_SIZE,
+    },
     lang::Language,
+    runner::check_path,
+    shell::shell_language,
+    sql::{run_query, sql_language},
 };
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// List every file under `dir`, by their path relative to `dir`
+fn walk(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut stack = vec![PathBuf::new()];
+    while let Some(rel) = stack.pop() {
+        let path = dir.join(&rel);
+        for entry in fs::read_dir(&path)
+            .with_context(|| format!("While reading directory `{}`", path.display()))?
+        {
+            let rel = rel.join(entry?.file_name());
+            if dir.join(&rel).is_dir() {
+                stack.push(rel);
+                continue;
+            }
+            let name = rel.to_string_lossy().replace('\\', "/");
+            check_path(&name)?;
+            files.push((name, dir.join(rel)));
+        }
+    }
+    Ok(files)
+}
+
+/// Read the starter files in `dir`
+fn read_files(dir: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let files = walk(dir)?;
+    anyhow::ensure!(
+        files.len() <= MAX_FILES,
+        "Expected at most {MAX_FILES} starter files, found {}",
+        files.len()
+    );
+
+    let mut out = HashMap::new();
+    for (name, path) in files {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("While reading file `{}`", path.display()))?;
+        anyhow::ensure!(
+            content.len() <= MAX_FILE_SIZE,
+            "Starter file `{name}` is larger than {MAX_FILE_SIZE} bytes"
+        );
+        out.insert(name, content);
+    }
+    Ok(out)
+}
+
+/// Read the fixtures in `dir`, which can be binary
+fn read_fixtures(dir: &Path) -> anyhow::Result<HashMap<String, Vec<u8>>> {
+    let mut out = HashMap::new();
+    let mut size = 0;
+    for (name, path) in walk(dir)? {
+        let content =
+            fs::read(&path).with_context(|| format!("While reading file `{}`", path.display()))?;
+        size += content.len();
+        anyhow::ensure!(
+            size <= MAX_FIXTURES_SIZE,
+            "Fixtures add up to more than {MAX_FIXTURES_SIZE} bytes"
+        );
+        out.insert(name, content);
+    }
+    Ok(out)
+}
+
+/// The starter files that go with code written in `lang`: code files in other
+/// languages are left out.
+fn files_for(files: &HashMap<String, String>, lang: &Language) -> HashMap<String, String> {
+    files
+        .iter()
+        .filter(|(path, _)| {
+            let ext = Path::new(path)
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy();
+            FileType::from_ext(&ext) != FileType::Code || ext == lang.extension()
+        })
+        .map(|(path, content)| (path.clone(), content.clone()))
+        .collect()
+}
 
 impl FromDirectory for Exercise {
     fn from_directory(
@@ -19,9 +107,25 @@ impl FromDirectory for Exercise {
 
         let id = context.id().rsplit_once('/').unwrap().1.to_string();
         let starting_code = content.query_files("template", FileType::Code)?;
+        let starting_files = match content.get_directory("template") {
+            Ok(dir) => read_files(&dir).context("While reading `template` directory")?,
+            Err(_) => HashMap::new(),
+        };
+        let fixtures = match content.get_directory("files") {
+            Ok(dir) => read_fixtures(&dir).context("While reading `files` directory")?,
+            Err(_) => HashMap::new(),
+        };
+        if let Some(path) = starting_files.keys().find(|p| fixtures.contains_key(*p)) {
+            anyhow::bail!("`{path}` is both a starter file and a fixture");
+        }
         let generator = content
-            .query_files("generator", FileType::Code)?
-            .collect::<Vec<_>>();
+            .query_files("generator", FileType::Code)
+            .ok()
+            .and_then(|mut files| files.next());
+        let solution = content
+            .query_files("solution", FileType::Code)
+            .ok()
+            .and_then(|mut files| files.next());
 
         let mut config: ExerciseConfig =
             toml::from_str(&config.read_to_string()?).context("While parsing `config.toml`")?;
@@ -31,21 +135,195 @@ impl FromDirectory for Exercise {
             .values_mut()
             .for_each(|f| f.seed = context.next_seed());
 
-        let lang = Language::from_str(&generator[0].ext)?;
-        let content = fs::read_to_string(generator[0].path())
-            .context("While reading test case generator file")?;
+        let solution = if let Some(sql) = &mut config.sql {
+            let solution =
+                solution.context("Expected a `solution.sql` file with the reference query")?;
+            let code = solution.read_to_string()?;
+            sql.schema = content
+                .query_file("schema", FileType::Code)?
+                .read_to_string()?;
+            let expected = run_query(cfg, &sql.schema, &code, &fixtures)
+                .context("While running reference query")?
+                .map_err(|e| anyhow::anyhow!("Reference query failed:\n{e}"))?;
+            sql.expected = Some(expected);
+
+            Some(Solution {
+                lang: sql_language()?,
+                code,
+                files: HashMap::new(),
+            })
+        } else if let Some(shell) = &config.shell {
+            // the tests say what to expect, so the reference script only has to pass them
+            match solution {
+                Some(solution) => {
+                    let code = solution.read_to_string()?;
+                    let outputs = shell
+                        .run(cfg, &code, &fixtures)
+                        .context("While running reference solution")?;
+                    for (i, (test, output)) in shell.tests.iter().zip(&outputs).enumerate() {
+                        if let Some(reason) = test.check(output) {
+                            anyhow::bail!("Reference solution fails test {i}: {reason}");
+                        }
+                    }
+
+                    Some(Solution {
+                        lang: shell_language()?,
+                        code,
+                        files: HashMap::new(),
+                    })
+                }
+                None => None,
+            }
+        } else if let Some(mutation) = &mut config.mutation {
+            let solution = solution
+                .context("Expected a `solution.<code_ext>` file with the correct implementation")?;
+            let lang = Language::from_str(&solution.ext)?;
+            let dir = content
+                .get_directory("mutants")
+                .context("Expected a `mutants` directory with buggy implementations")?;
+            for (name, path) in walk(&dir)? {
+                let stem = name
+                    .strip_suffix(&format!(".{}", lang.extension()))
+                    .with_context(|| {
+                        format!(
+                            "Expected mutant `{name}` to be written in {}",
+                            lang.display_name()
+                        )
+                    })?;
+                let code = fs::read_to_string(&path)
+                    .with_context(|| format!("While reading mutant `{name}`"))?;
+                mutation.mutants.insert(stem.to_string(), code);
+            }
+            anyhow::ensure!(
+                !mutation.mutants.is_empty(),
+                "Expected at least one mutant in `mutants`"
+            );
+
+            let code = solution.read_to_string()?;
+            let files = files_for(&starting_files, &lang);
+            // reference tests have to pass on the solution and catch every mutant
+            if let Ok(tests) = content.query_file("tests", FileType::Code) {
+                let all_files = files
+                    .iter()
+                    .map(|(path, content)| (path.clone(), content.as_bytes()))
+                    .chain(
+                        fixtures
+                            .iter()
+                            .map(|(path, content)| (path.clone(), &content[..])),
+                    )
+                    .collect();
+                let results = mutation
+                    .run(&lang, cfg, &tests.read_to_string()?, &code, &all_files)
+                    .context("While running reference tests")?;
+                anyhow::ensure!(
+                    results.correct_passes,
+                    "Reference tests fail on the solution:\n{}{}",
+                    results.stdout,
+                    results.stderr
+                );
+                anyhow::ensure!(
+                    results.survived.is_empty(),
+                    "Reference tests don't catch mutants: {}",
+                    results.survived.join(", ")
+                );
+            }
+
+            Some(Solution { lang, code, files })
+        } else {
+            for (name, class) in &config.classes {
+                anyhow::ensure!(
+                    !config.functions.contains_key(name),
+                    "`{name}` is both a function and a class"
+                );
+                class
+                    .check()
+                    .with_context(|| format!("While checking tests for class `{name}`"))?;
+            }
+
+            // variants are solved per student by the reference solution
+            anyhow::ensure!(
+                !config.functions.values().any(|f| f.variants)
+                    || (generator.is_none() && solution.is_some()),
+                "Functions with `variants` generate their test cases from `constraints`, and \
+                 need a `solution.<code_ext>` file instead of a generator"
+            );
+
+            match (generator, solution) {
+                _ if config.functions.is_empty() => {}
+                (Some(generator), _) => {
+                    let lang = Language::from_str(&generator.ext)?;
+                    let content = fs::read_to_string(generator.path())
+                        .context("While reading test case generator file")?;
+
+                    generate(&lang, cfg, &content, &fixtures, &mut config)
+                        .context("While generating test cases")?;
+                }
+                (None, Some(_)) => {
+                    for (name, func) in config.functions.iter_mut() {
+                        func.tests = generate_cases(func).with_context(|| {
+                            format!("While generating test cases for function `{name}`")
+                        })?;
+                    }
+                }
+                (None, None) => anyhow::bail!(
+                    "Expected either a `generator.<code_ext>` file, or a `solution.<code_ext>` file \
+                     to compute the outputs of generated test cases"
+                ),
+            }
+
+            // the large inputs are timed against the reference solution
+            anyhow::ensure!(
+                !config.functions.values().any(|f| f.performance.is_some()) || solution.is_some(),
+                "Functions with `performance` tests need a `solution.<code_ext>` file to time"
+            );
+            for (name, func) in config.functions.iter_mut() {
+                let Some(perf) = &func.performance else {
+                    continue;
+                };
+                let tests = perf.generate_cases(func).with_context(|| {
+                    format!("While generating large inputs for function `{name}`")
+                })?;
+                func.performance.as_mut().unwrap().tests = tests;
+            }
+
+            // the reference solution always has the final say on what the outputs are
+            match solution {
+                Some(solution) => {
+                    let lang = Language::from_str(&solution.ext)?;
+                    let code = fs::read_to_string(solution.path())
+                        .context("While reading reference solution file")?;
+
+                    let files = files_for(&starting_files, &lang);
 
-        generate(&lang, cfg, &content, &mut config).context("While generating test cases")?;
+                    solve(&lang, cfg, &code, &files, &fixtures, &mut config)
+                        .context("While running reference solution")?;
+                    Some(Solution { lang, code, files })
+                }
+                None => None,
+            }
+        };
 
         let iter = starting_code
             .filter_map(|item| Language::from_str(&item.ext).ok().map(|x| (item, x)))
             .map(|(item, lang)| -> anyhow::Result<_> {
+                let code = fs::read_to_string(item.path()).context("Expected valid path")?;
+                let regions = split_regions(&code, &lang.config().delimiter);
                 Ok((
                     lang,
                     LanguageInfo {
-                        code: fs::read_to_string(item.path()).context("Expected valid path")?,
-                        runner: runner_template(&lang, &config, &id)
-                            .context("While generating runner template")?,
+                        // without the delimiters, for anything that doesn't show regions
+                        code: match regions.is_empty() {
+                            true => code,
+                            false => regions.iter().map(|r| r.code.as_str()).collect(),
+                        },
+                        files: files_for(&starting_files, &lang),
+                        runner: match config.uses_runner() {
+                            true => runner_template(&lang, &config, &id)
+                                .context("While generating runner template")?,
+                            false => String::new(),
+                        },
+                        available: true,
+                        regions,
                     },
                 ))
             });
@@ -65,7 +343,9 @@ impl FromDirectory for Exercise {
         config.instructions = parse_md(&instructions.read_to_string()?, context)
             .context("While parsing markdown for `instructions.md`")?;
 
-        Ok(Exercise::new(config, lang_info))
+        let mut exercise = Exercise::new(config, lang_info, fixtures);
+        exercise.solution = solution;
+        Ok(exercise)
     }
 }
 
@@ -73,4 +353,10 @@ pub fn transform(exercise: &mut Exercise) {
     for (_, cfg) in exercise.config.functions.iter_mut() {
         cfg.tests.retain(|test| !test.hidden);
     }
+    for cfg in exercise.config.classes.values_mut() {
+        cfg.tests.retain(|test| !test.hidden);
+    }
+    if let Some(shell) = &mut exercise.config.shell {
+        shell.tests.retain(|test| !test.hidden);
+    }
 }
@@ -9,6 +9,7 @@ use std::fs::{self, File};
 
 pub mod article;
 pub mod exercise;
+pub mod parsons;
 pub mod quiz;
 pub mod utils;
 
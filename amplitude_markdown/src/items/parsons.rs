@@ -0,0 +1,338 @@
+use super::*;
+
+use crate::parse::parse_md;
+use amplitude_runner::{
+    constraint::generate_cases,
+    exercise::{
+        runner_template, solve, Exercise, ExerciseConfig, FunctionConfig, LanguageInfo, Submission,
+        TestResults,
+    },
+    lang::Language,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+/// How the order a student puts the lines in is checked
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Grading {
+    /// The lines have to be in the same order as the solution's
+    #[default]
+    Order,
+    /// The lines are put together and run against test cases
+    Run,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParsonsConfig {
+    pub title: String,
+    /// Lines that aren't part of the solution, mixed in with the rest
+    #[serde(default)]
+    pub distractors: Vec<String>,
+    #[serde(default)]
+    pub grading: Grading,
+    /// The functions to test when grading by running the lines. Their outputs
+    /// come from the solution.
+    #[serde(default)]
+    pub functions: HashMap<String, FunctionConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Line {
+    /// Stays the same between requests, without giving away where the line
+    /// goes
+    pub id: String,
+    /// The line with its indentation
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Parsons {
+    pub title: String,
+    pub instructions: String,
+    pub lang: Language,
+    pub grading: Grading,
+    /// The lines of the solution and the distractors, shuffled
+    pub lines: Vec<Line>,
+    /// The lines of the solution in order
+    #[serde(skip)]
+    pub solution: Vec<String>,
+    /// What the lines are run as, when grading by running them
+    #[serde(skip)]
+    pub exercise: Option<Exercise>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsonsResult {
+    pub passed: bool,
+    /// The test results, when grading by running the lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<HashMap<String, TestResults>>,
+}
+
+/// Split `solution` and `distractors` into shuffled lines, ignoring blank
+/// ones. Also returns the lines of the solution in order. `secret` is hashed
+/// into the ids, which would otherwise give away the order.
+pub fn shuffle_lines(
+    id: &str,
+    solution: &str,
+    distractors: &[String],
+    seed: u64,
+    secret: u64,
+) -> (Vec<Line>, Vec<String>) {
+    let solution = solution
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim_end().to_string())
+        .collect::<Vec<_>>();
+
+    let mut lines = solution
+        .iter()
+        .chain(distractors.iter().filter(|line| !line.trim().is_empty()))
+        .enumerate()
+        .map(|(i, code)| {
+            let hash = Sha256::new()
+                .chain_update(secret.to_le_bytes())
+                .chain_update(id)
+                .chain_update([0])
+                .chain_update(i.to_le_bytes())
+                .finalize();
+            Line {
+                id: hash[..8].iter().map(|b| format!("{b:02x}")).collect(),
+                code: code.trim_end().to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+    lines.shuffle(&mut StdRng::seed_from_u64(seed ^ secret));
+
+    (lines, solution)
+}
+
+impl Parsons {
+    /// The lines with the given `ids`, in order
+    pub fn code(&self, ids: &[String]) -> anyhow::Result<Vec<&str>> {
+        let mut seen = HashSet::new();
+        ids.iter()
+            .map(|id| {
+                anyhow::ensure!(seen.insert(id), "Line `{id}` is used more than once");
+                self.lines
+                    .iter()
+                    .find(|line| &line.id == id)
+                    .map(|line| line.code.as_str())
+                    .with_context(|| format!("Line `{id}` not found"))
+            })
+            .collect()
+    }
+
+    /// Check the lines with the given `ids`, in that order. `id` is what the
+    /// code is run as.
+    pub fn evaluate(
+        &self,
+        ids: &[String],
+        id: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<ParsonsResult> {
+        let code = self.code(ids)?;
+        match (self.grading, &self.exercise) {
+            (Grading::Order, _) => Ok(ParsonsResult {
+                // comparing the lines themselves, so identical lines can be swapped
+                passed: code == self.solution,
+                results: None,
+            }),
+            (Grading::Run, Some(exercise)) => {
+                let source = code
+                    .iter()
+                    .map(|line| format!("{line}\n"))
+                    .collect::<String>();
+                let results = exercise
+                    .run_tests(
+                        &self.lang,
+                        &Submission::Code(&source),
+                        &HashMap::new(),
+                        id,
+                        cfg,
+                    )
+                    .context("While running tests")?;
+                Ok(ParsonsResult {
+                    passed: results.passed(),
+                    results: Some(results.results),
+                })
+            }
+            (Grading::Run, None) => anyhow::bail!("Parsons problem has no tests to run"),
+        }
+    }
+}
+
+impl FromDirectory for Parsons {
+    fn from_directory(
+        content: &DirectoryContent,
+        context: &mut DataContext,
+        cfg: &Config,
+    ) -> anyhow::Result<Self> {
+        let instructions = content.query_file("instructions", FileType::Markdown)?;
+        let config = content.query_file("parsons", FileType::Toml)?;
+        let solution = content.query_file("solution", FileType::Code)?;
+
+        let id = context.id().rsplit_once('/').unwrap().1.to_string();
+        let lang = Language::from_str(&solution.ext)?;
+        let code = solution.read_to_string()?;
+        let config: ParsonsConfig =
+            toml::from_str(&config.read_to_string()?).context("While parsing `parsons.toml`")?;
+
+        let seed = context.next_seed();
+        let (lines, solution) = shuffle_lines(
+            context.id(),
+            &code,
+            &config.distractors,
+            seed,
+            context.secret(),
+        );
+        anyhow::ensure!(
+            !solution.is_empty(),
+            "Expected `solution.{}` to have at least one line",
+            lang.extension()
+        );
+
+        let exercise = match config.grading {
+            Grading::Order => {
+                anyhow::ensure!(
+                    config.functions.is_empty(),
+                    "`functions` are only used when grading by running the lines"
+                );
+                None
+            }
+            Grading::Run => {
+                anyhow::ensure!(
+                    !config.functions.is_empty(),
+                    "Expected `functions` to test when grading by running the lines"
+                );
+                let mut exercise_cfg = ExerciseConfig {
+                    title: config.title.clone(),
+                    instructions: String::new(),
+                    functions: config.functions,
+                    classes: HashMap::new(),
+                    sql: None,
+                    shell: None,
+                    mutation: None,
+                    coverage: false,
+                    style: None,
+                };
+                for (name, func) in exercise_cfg.functions.iter_mut() {
+                    func.seed = context.next_seed();
+                    func.tests = generate_cases(func).with_context(|| {
+                        format!("While generating test cases for function `{name}`")
+                    })?;
+                }
+                solve(
+                    &lang,
+                    cfg,
+                    &code,
+                    &HashMap::new(),
+                    &HashMap::new(),
+                    &mut exercise_cfg,
+                )
+                .context("While running reference solution")?;
+
+                let info = LanguageInfo {
+                    code: String::new(),
+                    files: HashMap::new(),
+                    runner: runner_template(&lang, &exercise_cfg, &id)
+                        .context("While generating runner template")?,
+                    available: true,
+                    regions: Vec::new(),
+                };
+                Some(Exercise::new(
+                    exercise_cfg,
+                    HashMap::from_iter([(lang, info)]),
+                    HashMap::new(),
+                ))
+            }
+        };
+
+        Ok(Parsons {
+            title: config.title,
+            instructions: parse_md(&instructions.read_to_string()?, context)
+                .context("While parsing markdown for `instructions.md`")?,
+            lang,
+            grading: config.grading,
+            lines,
+            solution,
+            exercise,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shuffle_lines() {
+        let solution = "def f(x):\n\n    y = x\n    return y\n";
+        let distractors = vec!["    return x + 1".to_string()];
+        let (lines, solution) = shuffle_lines("a/b", solution, &distractors, 0, 0);
+
+        assert_eq!(solution, vec!["def f(x):", "    y = x", "    return y"]);
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().any(|l| l.code == "    return x + 1"));
+        assert_eq!(
+            lines,
+            shuffle_lines(
+                "a/b",
+                "def f(x):\n    y = x\n    return y",
+                &distractors,
+                0,
+                0
+            )
+            .0
+        );
+        assert_ne!(
+            lines[0].id,
+            shuffle_lines("a/c", "def f(x):", &[], 0, 0).0[0].id
+        );
+        // the ids can't be worked out without the secret
+        let first = lines.iter().find(|l| l.code == "def f(x):").unwrap();
+        assert_eq!(
+            first.id,
+            shuffle_lines("a/b", "def f(x):", &[], 0, 0).0[0].id
+        );
+        assert_ne!(
+            first.id,
+            shuffle_lines("a/b", "def f(x):", &[], 0, 1).0[0].id
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        amplitude_common::config_and_set_path().unwrap();
+        let (lines, solution) = shuffle_lines("a/b", "x = 1\ny = 2\n", &[], 0, 0);
+        let parsons = Parsons {
+            title: "test".to_string(),
+            instructions: String::new(),
+            lang: "python".parse().unwrap(),
+            grading: Grading::Order,
+            lines,
+            solution,
+            exercise: None,
+        };
+        let id = |code: &str| {
+            parsons
+                .lines
+                .iter()
+                .find(|l| l.code == code)
+                .unwrap()
+                .id
+                .clone()
+        };
+
+        let code = parsons.code(&[id("y = 2"), id("x = 1")]).unwrap();
+        assert_eq!(code, vec!["y = 2", "x = 1"]);
+        assert!(parsons.code(&[id("x = 1"), id("x = 1")]).is_err());
+        assert!(parsons.code(&["nope".to_string()]).is_err());
+    }
+}
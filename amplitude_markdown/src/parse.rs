@@ -3,8 +3,8 @@ pub mod inject;
 pub mod link_concat;
 
 use crate::{
-    items::article::parse_frontmatter,
-    path::{DirectoryContent, FromDirectory},
+    items::{article::parse_frontmatter, parsons::Parsons},
+    path::{DirectoryContent, FileType, FromDirectory},
     OsStrToString,
 };
 use amplitude_common::{
@@ -63,6 +63,8 @@ pub struct CategoryConfig {
     pub description: String,
     #[serde(skip_deserializing)]
     pub exercises: Vec<String>,
+    #[serde(skip_deserializing)]
+    pub parsons: Vec<String>,
 }
 
 /// Reparses the things and does the things
@@ -118,7 +120,7 @@ pub fn parse(config: &Config) -> anyhow::Result<ParseData> {
 
     info!("Parsing articles...");
 
-    let mut data = RawParseData::new(md_ctx);
+    let mut data = RawParseData::new(md_ctx, config.parse.secret);
     for item in fs::read_dir(&config.parse.clone_path)? {
         let item = item?;
         let path = item.path();
@@ -154,17 +156,32 @@ pub fn parse(config: &Config) -> anyhow::Result<ParseData> {
                     continue;
                 }
                 let exercise_id = path.file_name().to_string();
+                let content =
+                    DirectoryContent::new(&path).context("While getting directory content")?;
+
+                // directories with a `parsons.toml` are parsons problems, not exercises
+                if content.query_file("parsons", FileType::Toml).is_ok() {
+                    category
+                        .parsons
+                        .push(format!("{category_id}/{exercise_id}"));
+
+                    ctx.scope(&exercise_id, |ctx| -> anyhow::Result<()> {
+                        let parsons = Parsons::from_directory(&content, ctx, config)
+                            .context("While getting `Parsons`")?;
+                        ctx.add_parsons(parsons);
+                        Ok(())
+                    })
+                    .context("While parsing parsons problem")?;
+                    continue;
+                }
+
                 category
                     .exercises
                     .push(format!("{category_id}/{exercise_id}"));
 
                 ctx.scope(&exercise_id, |ctx| -> anyhow::Result<()> {
-                    let exercise = Exercise::from_directory(
-                        &DirectoryContent::new(&path).context("While getting directory content")?,
-                        ctx,
-                        config,
-                    )
-                    .context("While getting `Exercise`")?;
+                    let exercise = Exercise::from_directory(&content, ctx, config)
+                        .context("While getting `Exercise`")?;
 
                     ctx.add(exercise).context("While adding exercise")?;
 
@@ -257,19 +274,25 @@ pub struct RawParseData {
     // items: HashMap<String, ItemType>,
     // tracks: HashMap<String, Vec<Track>>,
     exercises: HashMap<String, Exercise>,
+    parsons: HashMap<String, Parsons>,
     tree: HashMap<String, Vec<String>>,
     markdown_context: MarkdownContext,
     pub seed: u64,
+    /// Mixed into the ids of parsons lines, so where a line goes can't be
+    /// worked out from its id
+    secret: u64,
 }
 
 impl RawParseData {
-    pub fn new(md_ctx: MarkdownContext) -> Self {
+    pub fn new(md_ctx: MarkdownContext, secret: u64) -> Self {
         Self {
             markdown_context: md_ctx,
             categories: default(),
             exercises: default(),
+            parsons: default(),
             tree: default(),
             seed: default(),
+            secret,
         }
     }
 }
@@ -281,6 +304,7 @@ pub struct ParseData {
     // pub items: HashMap<String, ItemType>,
     // pub tracks: HashMap<String, Vec<Track>>,
     pub exercises: HashMap<String, Exercise>,
+    pub parsons: HashMap<String, Parsons>,
     pub tree: HashMap<String, Vec<String>>,
 }
 
@@ -289,6 +313,7 @@ impl ParseData {
         Ok(Self {
             categories: data.categories,
             exercises: data.exercises,
+            parsons: data.parsons,
             tree: data.tree,
         })
     }
@@ -1,4 +1,5 @@
 use super::{CategoryConfig, parse_md, RawParseData};
+use crate::items::parsons::Parsons;
 use amplitude_runner::exercise::Exercise;
 use comrak::{ComrakOptions, RefMap};
 use tracing::debug;
@@ -23,6 +24,11 @@ impl<'a> DataContext<'a> {
         seed
     }
 
+    /// A random number kept in the database, never sent to clients
+    pub fn secret(&self) -> u64 {
+        self.context.secret
+    }
+
     /// Add an exercise to the context
     pub fn add(&mut self, exercise: Exercise) -> anyhow::Result<()> {
         debug!("{:24} (id: {})", "Adding exercise to context", &self.id);
@@ -46,6 +52,12 @@ impl<'a> DataContext<'a> {
         Ok(())
     }
 
+    /// Add a parsons problem to the context
+    pub fn add_parsons(&mut self, parsons: Parsons) {
+        debug!("{:24} (id: {})", "Adding parsons to context", &self.id);
+        self.context.parsons.insert(self.id.clone(), parsons);
+    }
+
     pub fn add_category(&mut self, category: CategoryConfig) {
         debug!("{:24} (id: {})", "Adding category to context", &self.id);
         self.context.categories.insert(self.id.clone(), category);
@@ -2,7 +2,6 @@ use crate::{parse::context::DataContext, OsStrToString};
 use amplitude_common::config::Config;
 use amplitude_runner::lang::Language;
 use anyhow::Context;
-use enum_iterator::all;
 use std::{
     fmt::{self, Display},
     fs::File,
@@ -40,15 +39,12 @@ pub fn from_directory<T: FromDirectory>(
     T::from_directory(&content, context, cfg)
 }
 
-#[ctor::ctor]
-static CODE_EXT: Vec<&'static str> = all::<Language>().map(|l| l.extension()).collect();
-
 impl FileType {
     pub fn from_ext(ext: &str) -> Self {
         match ext {
             "md" => FileType::Markdown,
             "toml" => FileType::Toml,
-            _ if CODE_EXT.contains(&ext) => FileType::Code,
+            _ if Language::from_extension(ext).is_some() => FileType::Code,
             _ => FileType::Other,
         }
     }
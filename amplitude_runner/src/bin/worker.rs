@@ -0,0 +1,221 @@
+//! Runs code sent over http by amplitude servers on other machines
+//!
+//! ```text
+//! POST /run       a `Job`, responding with its `RunOutput`
+//! GET  /health    the languages that can be run
+//! GET  /capacity  how many jobs are running, out of how many can be at once
+//! ```
+
+use std::{collections::HashSet, env, fs, net::IpAddr, path::PathBuf, process, thread};
+
+use afire::{HeaderType, Method, Request, Response, Server, Status};
+use amplitude_common::{
+    config::{DockerConfig, WorkersConfig},
+    path,
+};
+use amplitude_runner::{
+    health::check_languages,
+    worker::{Health, Job, Slots},
+};
+use anyhow::Context;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+/// Run code sent by amplitude servers
+#[derive(Parser, Debug)]
+struct Cli {
+    /// The path of the config file
+    #[arg(long, default_value = "config.toml")]
+    config: PathBuf,
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    #[arg(long, default_value_t = 8081)]
+    port: u16,
+    /// How many jobs can run at once, the number of cpus by default
+    #[arg(long)]
+    slots: Option<usize>,
+    /// How many requests can be handled at once, including the running jobs.
+    /// Requests past this wait for a thread, so there should be plenty more
+    /// than `slots` for `/health` and turning away jobs.
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+/// The parts of the config file we care about
+#[derive(Deserialize, Debug)]
+struct WorkerConfig {
+    docker: DockerConfig,
+    #[serde(default)]
+    workers: WorkersConfig,
+}
+
+struct Worker {
+    docker: DockerConfig,
+    token: Option<String>,
+    /// The languages that passed the health check on startup
+    languages: HashSet<String>,
+    slots: Slots,
+}
+
+impl Worker {
+    fn authorized(&self, req: &Request) -> bool {
+        match &self.token {
+            Some(token) => {
+                req.headers.get(HeaderType::Authorization)
+                    == Some(format!("Bearer {token}").as_str())
+            }
+            None => true,
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = start(cli) {
+        eprintln!("error: {e:#}");
+        process::exit(1);
+    }
+}
+
+fn start(cli: Cli) -> anyhow::Result<()> {
+    // allow running from inside `amplitude_runner` with `cargo r`
+    if !path::LANGUAGES.as_path().exists() {
+        env::set_current_dir("..").context("While moving to the parent directory")?;
+    }
+    anyhow::ensure!(
+        path::LANGUAGES.as_path().exists(),
+        "Could not find the `{}` directory",
+        path::LANGUAGES
+    );
+
+    let mut config: WorkerConfig = toml::from_str(
+        &fs::read_to_string(&cli.config)
+            .with_context(|| format!("While reading `{}`", cli.config.display()))?,
+    )
+    .context("While parsing config file")?;
+    config.docker.load_languages()?;
+    anyhow::ensure!(
+        config.workers.token.is_some() || is_loopback(&cli.host),
+        "Set `token` under `[workers]` to listen on `{}`, so not just anyone can run code",
+        cli.host
+    );
+    fs::create_dir_all(&config.docker.tmp_folder).context("While creating temp dir")?;
+
+    println!("Checking languages...");
+    let mut languages = HashSet::new();
+    for (lang, result) in check_languages(&config.docker) {
+        match result {
+            Ok(_) => {
+                languages.insert(lang);
+            }
+            Err(e) => eprintln!("Language `{lang}` is unavailable: {e:#}"),
+        }
+    }
+    let mut available = languages.iter().collect::<Vec<_>>();
+    available.sort();
+    println!("Available languages: {available:?}");
+
+    let slots = match cli.slots {
+        Some(slots) => slots,
+        None => thread::available_parallelism()
+            .context("While getting the number of cpus")?
+            .get(),
+    };
+    let threads = cli.threads.unwrap_or(slots * 2 + 16);
+    anyhow::ensure!(
+        threads > slots,
+        "`--threads` has to be more than `--slots`, so requests can be answered while every slot is busy"
+    );
+    let worker = Worker {
+        docker: config.docker,
+        token: config.workers.token,
+        languages,
+        slots: Slots::new(slots),
+    };
+
+    let mut server = Server::<Worker>::new(&cli.host, cli.port).state(worker);
+    attach(&mut server);
+
+    println!(
+        "Listening on {}:{} with {slots} slots and {threads} threads",
+        cli.host, cli.port
+    );
+    // jobs past the limit are turned away by the spare threads, which are
+    // never taken by running jobs
+    server
+        .start_threaded(threads)
+        .map_err(|e| anyhow::anyhow!("While starting server: {e:?}"))?;
+    Ok(())
+}
+
+/// Whether only this machine can connect to `host`
+fn is_loopback(host: &str) -> bool {
+    host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn attach(server: &mut Server<Worker>) {
+    server.stateful_route(Method::POST, "/run", |worker, req| {
+        if !worker.authorized(req) {
+            return Response::new()
+                .status(Status::Unauthorized)
+                .text("Bad token");
+        }
+        let job = match serde_json::from_slice::<Job>(&req.body) {
+            Ok(job) => job,
+            Err(e) => {
+                return Response::new()
+                    .status(Status::BadRequest)
+                    .text(format!("Invalid job: {e}"))
+            }
+        };
+        // the server tries another worker for these
+        if !worker.languages.contains(&job.lang) {
+            return Response::new()
+                .status(Status::ServiceUnavailable)
+                .text(format!("Language `{}` is unavailable", job.lang));
+        }
+        let Some(_slot) = worker.slots.take() else {
+            return Response::new()
+                .status(Status::ServiceUnavailable)
+                .text("No free slots");
+        };
+
+        match job.run(&worker.docker) {
+            Ok(output) => json(&output),
+            Err(e) => Response::new()
+                .status(Status::InternalServerError)
+                .text(format!("{e:#}")),
+        }
+    });
+
+    server.stateful_route(Method::GET, "/health", |worker, req| {
+        if !worker.authorized(req) {
+            return Response::new()
+                .status(Status::Unauthorized)
+                .text("Bad token");
+        }
+        let mut languages = worker.languages.iter().cloned().collect::<Vec<_>>();
+        languages.sort();
+        json(&Health { languages })
+    });
+
+    server.stateful_route(Method::GET, "/capacity", |worker, req| {
+        if !worker.authorized(req) {
+            return Response::new()
+                .status(Status::Unauthorized)
+                .text("Bad token");
+        }
+        json(&worker.slots.capacity())
+    });
+}
+
+fn json(data: &impl Serialize) -> Response {
+    match serde_json::to_string(data) {
+        Ok(json) => Response::new()
+            .header("Content-Type", "application/json")
+            .text(json),
+        Err(e) => Response::new()
+            .status(Status::InternalServerError)
+            .text(e.to_string()),
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{exercise::Exercise, lang::Language};
+
+/// Changes whenever anything about `exercise` that could change the result of
+/// evaluating a submission in `lang` does
+pub fn exercise_version(exercise: &Exercise, lang: &Language) -> String {
+    let runner = exercise.lang_info.get(lang).map(|info| &info.runner);
+    let solution = exercise.solution.as_ref().map(|s| {
+        json!([
+            s.lang.name(),
+            s.code,
+            s.files.iter().collect::<BTreeMap<_, _>>()
+        ])
+    });
+
+    let mut hash =
+        Sha256::new().chain_update(json!([exercise.config, runner, solution]).to_string());
+    for (path, content) in exercise.fixtures.iter().collect::<BTreeMap<_, _>>() {
+        hash.update(path);
+        hash.update([0]);
+        hash.update((content.len() as u64).to_le_bytes());
+        hash.update(content);
+    }
+    hex(&hash.finalize())
+}
+
+/// What the result of evaluating `source`, the whole submission, and the
+/// student's other `files` is cached under
+pub fn cache_key(
+    exercise_id: &str,
+    version: &str,
+    lang: &Language,
+    source: &str,
+    files: &HashMap<String, String>,
+) -> String {
+    let hash = Sha256::new()
+        .chain_update(exercise_id)
+        .chain_update([0])
+        .chain_update(version)
+        .chain_update([0])
+        .chain_update(lang.name())
+        .chain_update([0])
+        .chain_update(json!([source, files.iter().collect::<BTreeMap<_, _>>()]).to_string())
+        .finalize();
+    hex(&hash)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Keeps the `capacity` most recently used results, as json
+pub struct EvaluationCache {
+    capacity: usize,
+    inner: Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    /// Each result, and when it was last used
+    entries: HashMap<String, (String, u64)>,
+    /// The keys of the results, by when they were last used
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl EvaluationCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Lru::default()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut lru = self.inner.lock().unwrap();
+        let Lru {
+            entries,
+            order,
+            tick,
+        } = &mut *lru;
+        let (result, used) = entries.get_mut(key)?;
+        order.remove(used);
+        *tick += 1;
+        *used = *tick;
+        order.insert(*tick, key.to_string());
+        Some(result.clone())
+    }
+
+    pub fn insert(&self, key: String, result: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lru = self.inner.lock().unwrap();
+        lru.tick += 1;
+        let tick = lru.tick;
+        if let Some((_, used)) = lru.entries.insert(key.clone(), (result, tick)) {
+            lru.order.remove(&used);
+        }
+        lru.order.insert(tick, key);
+
+        while lru.entries.len() > self.capacity {
+            let (_, oldest) = lru.order.pop_first().unwrap();
+            lru.entries.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    #[test]
+    fn test_lru() {
+        let cache = EvaluationCache::new(2);
+        cache.insert("a".to_string(), "1".to_string());
+        cache.insert("b".to_string(), "2".to_string());
+        assert_eq!(cache.get("a"), Some("1".to_string()));
+
+        // `b` was used least recently
+        cache.insert("c".to_string(), "3".to_string());
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some("1".to_string()));
+        assert_eq!(cache.get("c"), Some("3".to_string()));
+
+        cache.insert("a".to_string(), "4".to_string());
+        cache.insert("d".to_string(), "5".to_string());
+        assert_eq!(cache.get("a"), Some("4".to_string()));
+        assert_eq!(cache.get("c"), None);
+
+        let cache = EvaluationCache::new(0);
+        cache.insert("a".to_string(), "1".to_string());
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_cache_key() {
+        config_and_set_path().unwrap();
+        let lang: Language = "python".parse().unwrap();
+        let files = HashMap::from_iter([("util.py".to_string(), "x = 1".to_string())]);
+        let key = cache_key("a/b", "v1", &lang, "code", &files);
+        assert_eq!(key, cache_key("a/b", "v1", &lang, "code", &files));
+        assert_ne!(key, cache_key("a/b", "v2", &lang, "code", &files));
+        assert_ne!(key, cache_key("a/c", "v1", &lang, "code", &files));
+        assert_ne!(key, cache_key("a/b", "v1", &lang, "code ", &files));
+        assert_ne!(key, cache_key("a/b", "v1", &lang, "code", &HashMap::new()));
+    }
+}
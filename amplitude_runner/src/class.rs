@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    exercise::{TestOutput, TestResult},
+    var_type::VariableType,
+};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassConfig {
+    /// The inputs of the constructor
+    #[serde(default)]
+    pub constructor: Vec<VariableType>,
+    pub methods: HashMap<String, MethodConfig>,
+    pub tests: Vec<ClassTest>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodConfig {
+    pub inputs: Vec<VariableType>,
+    /// What the method returns, if anything
+    #[serde(default)]
+    pub output: Option<VariableType>,
+}
+
+/// A new instance of the class, and the methods called on it in order
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassTest {
+    #[serde(default)]
+    pub constructor: Vec<Value>,
+    pub calls: Vec<MethodCall>,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodCall {
+    pub method: String,
+    #[serde(default)]
+    pub inputs: Vec<Value>,
+    /// What the call should return, filled in by the reference solution if
+    /// there is one
+    #[serde(default)]
+    pub output: Value,
+}
+
+impl ClassConfig {
+    /// Check that every test only calls methods that exist, with inputs and
+    /// expected outputs of the right types. For exercises without a reference
+    /// solution, so every output has to be given.
+    pub fn check(&self) -> anyhow::Result<()> {
+        let check_inputs = |types: &[VariableType], inputs: &[Value]| -> anyhow::Result<()> {
+            anyhow::ensure!(
+                types.len() == inputs.len(),
+                "Expected {} inputs, found {}",
+                types.len(),
+                inputs.len()
+            );
+            for (i, (ty, input)) in types.iter().zip(inputs).enumerate() {
+                ty.check(input)
+                    .with_context(|| format!("Input {i} is not a valid `{ty}`"))?;
+            }
+            Ok(())
+        };
+
+        for (i, test) in self.tests.iter().enumerate() {
+            check_inputs(&self.constructor, &test.constructor)
+                .with_context(|| format!("In the constructor of test {i}"))?;
+            for (j, call) in test.calls.iter().enumerate() {
+                let method = self
+                    .methods
+                    .get(&call.method)
+                    .with_context(|| format!("Method `{}` not found", call.method))
+                    .with_context(|| format!("In call {j} of test {i}"))?;
+                check_inputs(&method.inputs, &call.inputs)
+                    .with_context(|| format!("In call {j} of test {i}"))?;
+                match &method.output {
+                    Some(_) if call.output.is_null() => anyhow::bail!(
+                        "Call {j} of test {i} has no expected output, which is needed without a \
+                         solution"
+                    ),
+                    Some(ty) => ty.check(&call.output).with_context(|| {
+                        format!("The expected output of call {j} of test {i} is not a valid `{ty}`")
+                    })?,
+                    None => anyhow::ensure!(
+                        call.output.is_null(),
+                        "Call {j} of test {i} has an expected output, but `{}` doesn't return \
+                         anything",
+                        call.method
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// What the runner templates need to know about the tests, leaving out
+    /// the expected outputs
+    pub fn runner_tests(&self) -> Value {
+        self.tests
+            .iter()
+            .map(|test| {
+                json!({
+                    "hidden": test.hidden,
+                    "constructor": test.constructor,
+                    "calls": test.calls.iter().map(|call| json!({
+                        "method": call.method,
+                        "inputs": call.inputs,
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl ClassTest {
+    /// Compare what the runner returned for this test against the expected
+    /// outputs. `outputs` starts with the constructor, followed by a call for
+    /// every method up to the first exception.
+    pub fn compare(&self, outputs: &[TestOutput]) -> TestResult {
+        let mut stdout = String::new();
+        for (i, output) in outputs.iter().enumerate() {
+            match output {
+                TestOutput::Answer { value, stdout: out } => {
+                    stdout += out;
+                    // the constructor doesn't return anything
+                    if i == 0 {
+                        continue;
+                    }
+                    let Some(call) = self.calls.get(i - 1) else {
+                        return self.wrong_count(outputs, stdout);
+                    };
+                    if value != &call.output {
+                        return TestResult::Diverged {
+                            stdout,
+                            call: i - 1,
+                            output: value.clone(),
+                        };
+                    }
+                }
+                TestOutput::Exception {
+                    traceback,
+                    stdout: out,
+                } => {
+                    stdout += out;
+                    return TestResult::Error {
+                        traceback: traceback.clone(),
+                        stdout,
+                    };
+                }
+            }
+        }
+        if outputs.len() != self.calls.len() + 1 {
+            return self.wrong_count(outputs, stdout);
+        }
+        TestResult::Correct { stdout }
+    }
+
+    fn wrong_count(&self, outputs: &[TestOutput], stdout: String) -> TestResult {
+        TestResult::Error {
+            traceback: format!(
+                "Expected {} outputs, found {}",
+                self.calls.len() + 1,
+                outputs.len()
+            ),
+            stdout,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stack() -> ClassConfig {
+        toml::from_str(
+            r#"
+            [methods]
+            push = { inputs = ["int"] }
+            pop = { inputs = [], output = "int" }
+
+            [[tests]]
+            calls = [
+                { method = "push", inputs = [1] },
+                { method = "push", inputs = [2] },
+                { method = "pop", output = 2 },
+                { method = "pop", output = 1 },
+            ]
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn answer(value: Value) -> TestOutput {
+        TestOutput::Answer {
+            value,
+            stdout: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut stack = stack();
+        assert!(stack.check().is_ok());
+
+        stack.tests[0].calls[0].inputs = vec![json!("a")];
+        assert!(stack.check().is_err());
+        stack.tests[0].calls[0].inputs = vec![];
+        assert!(stack.check().is_err());
+        stack.tests[0].calls[0].method = "peek".to_string();
+        assert!(stack.check().is_err());
+    }
+
+    #[test]
+    fn test_check_outputs() {
+        // without a solution, every output has to be given
+        let mut outputs = stack();
+        outputs.tests[0].calls[2].output = Value::Null;
+        assert!(outputs.check().is_err());
+        outputs.tests[0].calls[2].output = json!("2");
+        assert!(outputs.check().is_err());
+        outputs.tests[0].calls[2].output = json!(2);
+        outputs.tests[0].calls[0].output = json!(1);
+        assert!(outputs.check().is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let test = &stack().tests[0];
+        let null = || answer(Value::Null);
+
+        assert!(matches!(
+            test.compare(&[null(), null(), null(), answer(json!(2)), answer(json!(1))]),
+            TestResult::Correct { .. }
+        ));
+        assert_eq!(
+            test.compare(&[null(), null(), null(), answer(json!(1)), answer(json!(2))]),
+            TestResult::Diverged {
+                stdout: String::new(),
+                call: 2,
+                output: json!(1),
+            }
+        );
+        assert!(matches!(
+            test.compare(&[
+                null(),
+                TestOutput::Exception {
+                    traceback: "oops".to_string(),
+                    stdout: String::new(),
+                }
+            ]),
+            TestResult::Error { .. }
+        ));
+        assert!(matches!(
+            test.compare(&[null(), null()]),
+            TestResult::Error { .. }
+        ));
+        assert!(matches!(
+            test.compare(&[
+                null(),
+                null(),
+                null(),
+                answer(json!(2)),
+                answer(json!(1)),
+                null()
+            ]),
+            TestResult::Error { .. }
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    exercise::{FunctionConfig, TestCase},
+    var_type::VariableType,
+};
+
+const DEFAULT_MIN: i64 = -100;
+const DEFAULT_MAX: i64 = 100;
+const DEFAULT_LEN: (usize, usize) = (0, 10);
+const DEFAULT_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// Constraints on a single function input, used to generate random inputs
+/// without having to write a generator.
+///
+/// ```toml
+/// inputs = ["int", "int[]"]
+/// constraints = [
+///     { min = 0, max = 10 },
+///     { len = [1, 20], unique = true, sorted = true, items = { min = -5, max = 50 } },
+/// ]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Constraints {
+    /// Inclusive lower bound of an `int` or `float`
+    pub min: Option<i64>,
+    /// Inclusive upper bound of an `int` or `float`
+    pub max: Option<i64>,
+    /// Inclusive length range of a `string` or an array
+    pub len: Option<(usize, usize)>,
+    /// Characters a `string` is made of
+    pub alphabet: Option<String>,
+    /// Whether the elements of an array should all be different
+    #[serde(default)]
+    pub unique: bool,
+    /// Whether the elements of an array should be in ascending order
+    #[serde(default)]
+    pub sorted: bool,
+    /// Constraints on the elements of an array
+    pub items: Option<Box<Constraints>>,
+    /// Constraints on the fields of a struct
+    #[serde(default)]
+    pub fields: HashMap<String, Constraints>,
+    /// Constraints on the elements of a tuple
+    #[serde(default)]
+    pub elements: Vec<Constraints>,
+}
+
+impl Constraints {
+    /// Check that every constraint makes sense for the type `ty`
+    pub fn validate(&self, ty: &VariableType) -> anyhow::Result<()> {
+        let numeric = matches!(ty, VariableType::Int | VariableType::Float);
+        let array = matches!(ty, VariableType::Array(_));
+
+        anyhow::ensure!(
+            numeric || (self.min.is_none() && self.max.is_none()),
+            "`min` and `max` only apply to `int` and `float`, not `{ty}`"
+        );
+        anyhow::ensure!(
+            array || matches!(ty, VariableType::String) || self.len.is_none(),
+            "`len` only applies to `string` and arrays, not `{ty}`"
+        );
+        anyhow::ensure!(
+            matches!(ty, VariableType::String) || self.alphabet.is_none(),
+            "`alphabet` only applies to `string`, not `{ty}`"
+        );
+        anyhow::ensure!(
+            array || (!self.unique && !self.sorted && self.items.is_none()),
+            "`unique`, `sorted` and `items` only apply to arrays, not `{ty}`"
+        );
+        anyhow::ensure!(
+            matches!(ty, VariableType::Struct(_)) || self.fields.is_empty(),
+            "`fields` only applies to structs, not `{ty}`"
+        );
+        anyhow::ensure!(
+            matches!(ty, VariableType::Tuple(_)) || self.elements.is_empty(),
+            "`elements` only applies to tuples, not `{ty}`"
+        );
+
+        let (min, max) = self.range();
+        anyhow::ensure!(min <= max, "`min` ({min}) is greater than `max` ({max})");
+        let (min, max) = self.len();
+        anyhow::ensure!(min <= max, "`len` has a minimum ({min}) greater than its maximum ({max})");
+        if let Some(alphabet) = &self.alphabet {
+            anyhow::ensure!(!alphabet.is_empty(), "`alphabet` is empty");
+        }
+
+        match ty {
+            VariableType::Array(inner) => {
+                if self.sorted {
+                    anyhow::ensure!(
+                        matches!(
+                            **inner,
+                            VariableType::Int | VariableType::Float | VariableType::String
+                        ),
+                        "`sorted` is only supported on arrays of `int`, `float` or `string`"
+                    );
+                }
+                if let Some(items) = &self.items {
+                    items.validate(inner)?;
+                }
+            }
+            VariableType::Struct(fields) => {
+                for (name, constraints) in &self.fields {
+                    let ty = fields
+                        .get(name)
+                        .ok_or_else(|| anyhow::anyhow!("Struct `{ty}` has no field `{name}`"))?;
+                    constraints.validate(ty)?;
+                }
+            }
+            VariableType::Tuple(types) => {
+                anyhow::ensure!(
+                    self.elements.len() <= types.len(),
+                    "Tuple `{ty}` has {} elements, but {} constraints were given",
+                    types.len(),
+                    self.elements.len()
+                );
+                for (constraints, ty) in self.elements.iter().zip(types) {
+                    constraints.validate(ty)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn range(&self) -> (i64, i64) {
+        (
+            self.min.unwrap_or(DEFAULT_MIN),
+            self.max.unwrap_or(DEFAULT_MAX),
+        )
+    }
+
+    fn len(&self) -> (usize, usize) {
+        self.len.unwrap_or(DEFAULT_LEN)
+    }
+
+    /// Generate a random value of type `ty` that fits these constraints
+    pub fn generate(&self, ty: &VariableType, rng: &mut impl Rng) -> anyhow::Result<Value> {
+        let (min, max) = self.range();
+        Ok(match ty {
+            VariableType::Int => json!(rng.gen_range(min..=max)),
+            VariableType::Float => json!(rng.gen_range(min as f64..=max as f64)),
+            VariableType::Boolean => json!(rng.gen_bool(0.5)),
+            VariableType::String => {
+                let alphabet = self
+                    .alphabet
+                    .as_deref()
+                    .unwrap_or(DEFAULT_ALPHABET)
+                    .chars()
+                    .collect::<Vec<_>>();
+                let (min, max) = self.len();
+                let len = rng.gen_range(min..=max);
+                let s = (0..len)
+                    .map(|_| *alphabet.choose(rng).unwrap())
+                    .collect::<String>();
+                json!(s)
+            }
+            VariableType::Array(inner) => {
+                let default = Constraints::default();
+                let items = self.items.as_deref().unwrap_or(&default);
+                let (min, max) = self.len();
+                let len = rng.gen_range(min..=max);
+
+                let mut values: Vec<Value> = Vec::with_capacity(len);
+                let mut attempts = 0;
+                while values.len() < len {
+                    anyhow::ensure!(
+                        attempts < len * 100,
+                        "Could not generate {len} unique values of type `{inner}`, try loosening \
+                         the constraints"
+                    );
+                    attempts += 1;
+
+                    let value = items.generate(inner, rng)?;
+                    if self.unique && values.contains(&value) {
+                        continue;
+                    }
+                    values.push(value);
+                }
+                if self.sorted {
+                    values.sort_by(compare);
+                }
+                Value::Array(values)
+            }
+            VariableType::Struct(fields) => {
+                let default = Constraints::default();
+                let mut map = serde_json::Map::new();
+                for (name, ty) in fields {
+                    let constraints = self.fields.get(name).unwrap_or(&default);
+                    map.insert(name.clone(), constraints.generate(ty, rng)?);
+                }
+                Value::Object(map)
+            }
+            VariableType::Tuple(types) => {
+                let default = Constraints::default();
+                let values = types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| self.elements.get(i).unwrap_or(&default).generate(ty, rng))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Value::Array(values)
+            }
+        })
+    }
+}
+
+/// Ordering used for `sorted` arrays, only numbers and strings are compared
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Generate the inputs for every test case of a function from its
+/// `constraints`, seeded by the function's seed.
+///
+/// The first `visible_cases` cases are visible, the rest are hidden. Outputs
+/// are left as `null`, to be filled in by running a reference solution.
+pub fn generate_cases(func: &FunctionConfig) -> anyhow::Result<Vec<TestCase>> {
+    anyhow::ensure!(
+        func.constraints.len() <= func.inputs.len(),
+        "Function has {} inputs, but {} constraints were given",
+        func.inputs.len(),
+        func.constraints.len()
+    );
+    let default = Constraints::default();
+    let constraints = (0..func.inputs.len())
+        .map(|i| func.constraints.get(i).unwrap_or(&default))
+        .collect::<Vec<_>>();
+    for (i, (c, ty)) in constraints.iter().zip(&func.inputs).enumerate() {
+        c.validate(ty)
+            .map_err(|e| e.context(format!("While validating constraints of input {i}")))?;
+    }
+
+    let mut rng = StdRng::seed_from_u64(func.seed);
+    (0..func.visible_cases + func.hidden_cases)
+        .map(|i| {
+            let inputs = constraints
+                .iter()
+                .zip(&func.inputs)
+                .map(|(c, ty)| c.generate(ty, &mut rng))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(TestCase {
+                inputs,
+                output: Value::Null,
+                hidden: i >= func.visible_cases,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ty(s: &str) -> VariableType {
+        VariableType::try_from(s).unwrap()
+    }
+
+    fn constraints(s: &str) -> Constraints {
+        toml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_generate_constrained() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let c = constraints("min = 3\nmax = 5");
+        for _ in 0..100 {
+            let v = c.generate(&ty("int"), &mut rng).unwrap().as_i64().unwrap();
+            assert!((3..=5).contains(&v));
+        }
+
+        let c = constraints("len = [2, 4]\nalphabet = \"xy\"");
+        for _ in 0..100 {
+            let v = c.generate(&ty("string"), &mut rng).unwrap();
+            let s = v.as_str().unwrap();
+            assert!((2..=4).contains(&s.len()));
+            assert!(s.chars().all(|c| c == 'x' || c == 'y'));
+        }
+
+        let c = constraints("len = [5, 5]\nunique = true\nsorted = true\nitems = { min = 0, max = 9 }");
+        for _ in 0..100 {
+            let v = c.generate(&ty("int[]"), &mut rng).unwrap();
+            let v = v
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_i64().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(v.len(), 5);
+            assert!(v.windows(2).all(|w| w[0] < w[1]));
+            assert!(v.iter().all(|x| (0..=9).contains(x)));
+        }
+    }
+
+    #[test]
+    fn test_validate_constraints() {
+        assert!(constraints("min = 0").validate(&ty("int")).is_ok());
+        assert!(constraints("min = 0").validate(&ty("string")).is_err());
+        assert!(constraints("min = 5\nmax = 0").validate(&ty("int")).is_err());
+        assert!(constraints("alphabet = \"\"").validate(&ty("string")).is_err());
+        assert!(constraints("sorted = true").validate(&ty("bool[]")).is_err());
+        assert!(constraints("items = { min = 0 }").validate(&ty("int[]")).is_ok());
+        assert!(constraints("items = { len = [0, 1] }").validate(&ty("int[]")).is_err());
+        assert!(constraints("fields = { a = { min = 0 } }")
+            .validate(&ty("{ a: int }"))
+            .is_ok());
+        assert!(constraints("fields = { b = { min = 0 } }")
+            .validate(&ty("{ a: int }"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_generate_cases() {
+        let func = FunctionConfig {
+            inputs: vec![ty("int"), ty("string")],
+            output: ty("int"),
+            constraints: vec![constraints("min = 0\nmax = 0")],
+            seed: 42,
+            hidden_cases: 3,
+            visible_cases: 2,
+            variants: false,
+            performance: None,
+            tests: vec![],
+        };
+        let cases = generate_cases(&func).unwrap();
+        assert_eq!(cases.len(), 5);
+        assert_eq!(cases.iter().filter(|c| c.hidden).count(), 3);
+        assert!(!cases[0].hidden && !cases[1].hidden);
+        assert!(cases.iter().all(|c| c.inputs[0] == 0 && c.output.is_null()));
+
+        let again = generate_cases(&func).unwrap();
+        assert!(cases.iter().zip(&again).all(|(a, b)| a.inputs == b.inputs));
+    }
+}
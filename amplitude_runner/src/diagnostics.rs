@@ -0,0 +1,365 @@
+use std::fmt;
+
+use amplitude_common::config::DiagnosticFormat;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "error" | "fatal error" => Some(Self::Error),
+            "warning" => Some(Self::Warning),
+            "note" | "help" => Some(Self::Note),
+            _ => None,
+        }
+    }
+}
+
+/// Something the compiler said about a line of a student's code
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The file it's about, `None` being the student's main code
+    pub file: Option<String>,
+    pub line: u32,
+    /// Starting from 1, if the compiler says
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Code that didn't compile
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CompileError {
+    /// What the compiler printed, to be shown as is
+    pub output: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Code failed to compile:\n{}", self.output)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// A diagnostic in whatever file the compiler says it's in
+struct Located {
+    path: String,
+    diagnostic: Diagnostic,
+}
+
+impl CompileError {
+    /// Parse the compiler's `output`, as [`parse`] does. Without a format,
+    /// there are no diagnostics.
+    pub fn parse(
+        format: Option<DiagnosticFormat>,
+        output: &str,
+        code: &str,
+        files: &[&str],
+    ) -> Self {
+        let (output, diagnostics) = match format {
+            Some(format) => parse(format, output, code, files),
+            None => (output.to_string(), Vec::new()),
+        };
+        Self {
+            output,
+            diagnostics,
+        }
+    }
+}
+
+/// Parse a compiler or linter's `output`, keeping the diagnostics in `code`,
+/// the student's main code, and the other `files` they wrote. Paths are
+/// relative to where the code is run. Also returns the output as it should be
+/// shown.
+pub fn parse(
+    format: DiagnosticFormat,
+    output: &str,
+    code: &str,
+    files: &[&str],
+) -> (String, Vec<Diagnostic>) {
+    let (output, located) = match format {
+        DiagnosticFormat::Gcc => (output.to_string(), parse_gcc(output)),
+        DiagnosticFormat::Javac => (output.to_string(), parse_javac(output)),
+        DiagnosticFormat::Pyflakes => (output.to_string(), parse_pyflakes(output)),
+        DiagnosticFormat::Rustc => parse_rustc(output),
+    };
+
+    let diagnostics = located
+        .into_iter()
+        .filter_map(
+            |Located {
+                 path,
+                 mut diagnostic,
+             }| {
+                let path = path.trim_start_matches("/runner/").trim_start_matches("./");
+                if path != code {
+                    diagnostic.file = Some(files.iter().find(|f| **f == path)?.to_string());
+                }
+                Some(diagnostic)
+            },
+        )
+        .collect();
+    (output, diagnostics)
+}
+
+/// Parse `file:line[:column]: rest`
+fn parse_location(line: &str) -> Option<(&str, u32, Option<u32>, &str)> {
+    let (path, rest) = line.split_once(':')?;
+    let (line, rest) = rest.split_once(':')?;
+    let line = line.parse().ok()?;
+    match rest
+        .split_once(':')
+        .and_then(|(column, rest)| Some((column.parse().ok()?, rest)))
+    {
+        Some((column, rest)) => Some((path, line, Some(column), rest)),
+        None => Some((path, line, None, rest)),
+    }
+}
+
+/// Parse `file:line[:column]: severity: message`
+fn parse_line(line: &str) -> Option<Located> {
+    let (path, line, column, rest) = parse_location(line)?;
+    let (severity, message) = rest.split_once(':')?;
+
+    Some(Located {
+        path: path.to_string(),
+        diagnostic: Diagnostic {
+            file: None,
+            line,
+            column,
+            severity: Severity::parse(severity.trim())?,
+            message: message.trim().to_string(),
+        },
+    })
+}
+
+fn parse_pyflakes(output: &str) -> Vec<Located> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (path, line, column, message) = parse_location(line)?;
+            Some(Located {
+                path: path.to_string(),
+                diagnostic: Diagnostic {
+                    file: None,
+                    line,
+                    column,
+                    severity: Severity::Warning,
+                    message: message.trim().to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+fn parse_gcc(output: &str) -> Vec<Located> {
+    output.lines().filter_map(parse_line).collect()
+}
+
+/// javac doesn't print columns, but puts a caret under them after the line
+/// of code
+fn parse_javac(output: &str) -> Vec<Located> {
+    let lines = output.lines().collect::<Vec<_>>();
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let mut located = parse_line(line)?;
+            if let Some(caret) = lines.get(i + 2).filter(|l| l.trim() == "^") {
+                located.diagnostic.column = Some(caret.find('^').unwrap() as u32 + 1);
+            }
+            Some(located)
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcMessage>,
+}
+
+#[derive(Deserialize)]
+struct RustcMessage {
+    message: String,
+    level: String,
+    spans: Vec<RustcSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: u32,
+    column_start: u32,
+    is_primary: bool,
+}
+
+/// Cargo prints a json message per line. The rendered messages are returned
+/// as the output, along with anything cargo printed that isn't json.
+fn parse_rustc(output: &str) -> (String, Vec<Located>) {
+    let mut rendered = String::new();
+    let mut located = Vec::new();
+    for line in output.lines() {
+        let Ok(msg) = serde_json::from_str::<CargoMessage>(line) else {
+            rendered += line;
+            rendered.push('\n');
+            continue;
+        };
+        let Some(msg) = msg.message.filter(|_| msg.reason == "compiler-message") else {
+            continue;
+        };
+
+        rendered += msg.rendered.as_deref().unwrap_or(&msg.message);
+        let span = msg.spans.iter().find(|s| s.is_primary);
+        if let (Some(span), Some(severity)) = (span, Severity::parse(&msg.level)) {
+            located.push(Located {
+                path: span.file_name.clone(),
+                diagnostic: Diagnostic {
+                    file: None,
+                    line: span.line_start,
+                    column: Some(span.column_start),
+                    severity,
+                    message: msg.message,
+                },
+            });
+        }
+    }
+    (rendered, located)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn diagnostic(
+        file: Option<&str>,
+        line: u32,
+        column: Option<u32>,
+        severity: Severity,
+        message: &str,
+    ) -> Diagnostic {
+        Diagnostic {
+            file: file.map(|f| f.to_string()),
+            line,
+            column,
+            severity,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_gcc() {
+        let output = "src/main.c: In function 'main':\n\
+                      src/main.c:3:5: error: expected ';' before 'return'\n\
+                      src/util.h:1:1: warning: data definition has no type\n\
+                      /usr/include/stdio.h:2:1: note: something in a header\n\
+                      /usr/bin/ld: undefined reference to `foo'\n";
+        let error = CompileError::parse(
+            Some(DiagnosticFormat::Gcc),
+            output,
+            "src/main.c",
+            &["src/util.h"],
+        );
+        assert_eq!(error.output, output);
+        assert_eq!(
+            error.diagnostics,
+            vec![
+                diagnostic(
+                    None,
+                    3,
+                    Some(5),
+                    Severity::Error,
+                    "expected ';' before 'return'"
+                ),
+                diagnostic(
+                    Some("src/util.h"),
+                    1,
+                    Some(1),
+                    Severity::Warning,
+                    "data definition has no type"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_javac() {
+        let output = "Main.java:3: error: ';' expected\n        int x = 1\n                 ^\n\
+                      Main.java:4: error: cannot find symbol\n        y = 2;\n        ^\n  \
+                      symbol:   variable y\n  location: class Main\n2 errors\n";
+        let error = CompileError::parse(Some(DiagnosticFormat::Javac), output, "Main.java", &[]);
+        assert_eq!(
+            error.diagnostics,
+            vec![
+                diagnostic(None, 3, Some(18), Severity::Error, "';' expected"),
+                diagnostic(None, 4, Some(9), Severity::Error, "cannot find symbol"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pyflakes() {
+        let output = "./main.py:1:1: 'os' imported but unused\n\
+                      ./util.py:4: undefined name 'x'\n\
+                      ./fixture.py:2:1: 'sys' imported but unused\n";
+        let (_, diagnostics) = parse(DiagnosticFormat::Pyflakes, output, "main.py", &["util.py"]);
+        assert_eq!(
+            diagnostics,
+            vec![
+                diagnostic(
+                    None,
+                    1,
+                    Some(1),
+                    Severity::Warning,
+                    "'os' imported but unused"
+                ),
+                diagnostic(
+                    Some("util.py"),
+                    4,
+                    None,
+                    Severity::Warning,
+                    "undefined name 'x'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rustc() {
+        let output = concat!(
+            r#"{"reason":"compiler-message","message":{"message":"cannot find value `x` in this scope","level":"error","spans":[{"file_name":"src/main.rs","line_start":2,"column_start":20,"is_primary":true}],"rendered":"error[E0425]: cannot find value `x`\n"}}"#,
+            "\n",
+            r#"{"reason":"compiler-message","message":{"message":"aborting due to previous error","level":"error","spans":[],"rendered":"error: aborting due to previous error\n"}}"#,
+            "\n",
+            r#"{"reason":"build-finished","success":false}"#,
+            "\n",
+            "error: could not compile `amplitude`\n",
+        );
+        let error = CompileError::parse(Some(DiagnosticFormat::Rustc), output, "src/main.rs", &[]);
+        assert_eq!(
+            error.output,
+            "error[E0425]: cannot find value `x`\nerror: aborting due to previous error\n\
+             error: could not compile `amplitude`\n"
+        );
+        assert_eq!(
+            error.diagnostics,
+            vec![diagnostic(
+                None,
+                2,
+                Some(20),
+                Severity::Error,
+                "cannot find value `x` in this scope"
+            )]
+        );
+    }
+}
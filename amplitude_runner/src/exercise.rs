@@ -1,14 +1,28 @@
-use crate::runner::{run, RunOutput};
-use crate::{lang::Language, var_type::VariableType};
+use crate::runner::{check_path, run, RunOptions, RunOutput};
+use crate::{
+    class::ClassConfig,
+    constraint::{generate_cases, Constraints},
+    diagnostics::CompileError,
+    lang::Language,
+    lint::{Style, StyleConfig},
+    mutation::{MutationConfig, MutationResults},
+    performance::{PerformanceConfig, PerformanceResult, Timed},
+    shell::ShellConfig,
+    sql::SqlConfig,
+    trace::{trace_options, Trace, TraceResult},
+    var_type::VariableType,
+    variant::{variant_seed, Variant},
+};
 use amplitude_common::config::Config;
 use amplitude_common::path;
 
 use anyhow::Context;
-use handlebars::Handlebars;
+use handlebars::{handlebars_helper, Handlebars};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::thread;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Field {
@@ -26,12 +40,25 @@ pub struct DynStruct {
 pub struct FunctionConfig {
     pub inputs: Vec<VariableType>,
     pub output: VariableType,
+    /// Constraints on each input, used to generate test cases when there is no
+    /// generator
+    #[serde(default)]
+    pub constraints: Vec<Constraints>,
     #[serde(skip)]
     pub seed: u64,
     #[serde(default = "hidden_cases_default")]
     pub hidden_cases: u32,
     #[serde(default = "visible_cases_default")]
     pub visible_cases: u32,
+    /// Gives every student their own visible test cases, generated from
+    /// `constraints` with a seed derived from the exercise and their user id.
+    /// Hidden test cases are the same for everyone.
+    #[serde(default)]
+    pub variants: bool,
+    /// Times the function on large hidden inputs, against the reference
+    /// solution
+    #[serde(default)]
+    pub performance: Option<PerformanceConfig>,
     #[serde(skip_deserializing)]
     pub tests: Vec<TestCase>,
 }
@@ -42,20 +69,119 @@ pub struct ExerciseConfig {
     pub title: String,
     #[serde(skip_deserializing)]
     pub instructions: String,
+    #[serde(default)]
     pub functions: HashMap<String, FunctionConfig>,
+    /// Classes checked by calling sequences of methods on them
+    #[serde(default)]
+    pub classes: HashMap<String, ClassConfig>,
+    /// Makes this a SQL exercise, where a query is checked instead of functions
+    #[serde(default)]
+    pub sql: Option<SqlConfig>,
+    /// Makes this a shell exercise, where a script is run against directory trees
+    #[serde(default)]
+    pub shell: Option<ShellConfig>,
+    /// Makes this an exercise where students write tests, which are run
+    /// against the reference solution and buggy versions of it
+    #[serde(default)]
+    pub mutation: Option<MutationConfig>,
+    /// Whether to collect which lines of the submission the visible test cases
+    /// run, for languages that support it
+    #[serde(default)]
+    pub coverage: bool,
+    /// Lints submissions, optionally scoring their style
+    #[serde(default)]
+    pub style: Option<StyleConfig>,
+}
+
+impl ExerciseConfig {
+    /// Whether the exercise is checked by a runner template calling functions
+    /// and classes, rather than some other way
+    pub fn uses_runner(&self) -> bool {
+        self.sql.is_none() && self.shell.is_none() && self.mutation.is_none()
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Exercise {
     pub config: ExerciseConfig,
     pub lang_info: HashMap<Language, LanguageInfo>,
+    /// Data files that are mounted next to the code on every run, by their path
+    /// relative to the code
+    #[serde(skip)]
+    pub fixtures: HashMap<String, Vec<u8>>,
+    /// The reference solution, if there is one
+    #[serde(skip)]
+    pub solution: Option<Solution>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub lang: Language,
+    pub code: String,
+    /// The starter files the solution was written against
+    pub files: HashMap<String, String>,
 }
 
+/// The most extra files a student can submit alongside their code
+pub const MAX_FILES: usize = 16;
+/// The most bytes a single submitted file can be
+pub const MAX_FILE_SIZE: usize = 64 * 1024;
+/// The most bytes the fixtures of an exercise can add up to
+pub const MAX_FIXTURES_SIZE: usize = 8 * 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct LanguageInfo {
     pub code: String,
+    /// Extra starter files, by their path relative to the code
+    pub files: HashMap<String, String>,
     #[serde(skip_serializing)]
     pub runner: String,
+    /// Whether the server is currently able to run this language
+    pub available: bool,
+    /// The template split into locked and editable regions, if it has any
+    /// delimiters. Only the editable ones can be changed by students.
+    pub regions: Vec<Region>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Region {
+    pub code: String,
+    pub editable: bool,
+}
+
+/// What a student sent in to be run
+#[derive(Debug, Clone, Copy)]
+pub enum Submission<'a> {
+    /// The whole file, for templates without regions
+    Code(&'a str),
+    /// The new contents of every editable region, in order
+    Edits(&'a [String]),
+}
+
+/// Split `code` into regions on lines that are just `delimiter`, alternating
+/// between locked and editable, starting locked. Templates without any
+/// delimiters have no regions.
+pub fn split_regions(code: &str, delimiter: &str) -> Vec<Region> {
+    let mut regions = vec![Region {
+        code: String::new(),
+        editable: false,
+    }];
+    for line in code.split_inclusive('\n') {
+        if line.trim() == delimiter {
+            let editable = !regions.last().unwrap().editable;
+            regions.push(Region {
+                code: String::new(),
+                editable,
+            });
+            continue;
+        }
+        regions.last_mut().unwrap().code += line;
+    }
+
+    if regions.len() == 1 {
+        return Vec::new();
+    }
+    regions
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
@@ -67,62 +193,238 @@ pub enum TestResult {
     Incorrect { stdout: String, output: Value },
     #[serde(rename = "error")]
     Error { traceback: String, stdout: String },
+    /// A method call on a class returned something unexpected, with the calls
+    /// after it not being checked
+    #[serde(rename = "diverged")]
+    Diverged {
+        stdout: String,
+        call: usize,
+        output: Value,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct TestResults {
     pub results: Vec<TestResult>,
     pub hidden: bool,
+    /// How the function did on the large inputs, for functions with
+    /// performance tests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub performance: Option<PerformanceResult>,
     pub passed: bool,
 }
 
+/// The lines of a submission that were and weren't run
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Coverage {
+    pub covered: BTreeSet<u32>,
+    pub uncovered: BTreeSet<u32>,
+}
+
+/// The results of running a submission against every test case
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Evaluation {
+    pub results: HashMap<String, TestResults>,
+    /// The lines run by the visible test cases, when the exercise collects
+    /// coverage
+    pub coverage: Option<Coverage>,
+    /// Why none of the tests were run, for code that didn't compile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compile_error: Option<CompileError>,
+    /// What the linter found, when the exercise lints submissions
+    pub style: Option<Style>,
+}
+
+impl Evaluation {
+    pub fn passed(&self) -> bool {
+        self.compile_error.is_none() && self.results.values().all(|r| r.passed)
+    }
+}
+
+/// The result of calling a function with custom inputs
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TryResult {
+    pub output: TestOutput,
+    /// What the reference solution returned, if there is one
+    pub expected: Option<TestOutput>,
+}
+
 impl Exercise {
-    pub fn new(config: ExerciseConfig, language_info: HashMap<Language, LanguageInfo>) -> Self {
+    pub fn new(
+        config: ExerciseConfig,
+        language_info: HashMap<Language, LanguageInfo>,
+        fixtures: HashMap<String, Vec<u8>>,
+    ) -> Self {
         Self {
             config,
             lang_info: language_info,
+            fixtures,
+            solution: None,
         }
     }
 
+    /// Check the extra files a student submitted alongside their code: their
+    /// paths have to be safe, and they can't replace the code, the runner or
+    /// the fixtures.
+    pub fn check_files(
+        &self,
+        lang: &Language,
+        files: &HashMap<String, String>,
+        id: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<()> {
+        let lang_cfg = cfg
+            .docker
+            .language_config
+            .get(lang.name())
+            .with_context(|| format!("Language `{}` not configured", lang.name()))?;
+        let code_file = format!("{id}.{}", lang.extension());
+
+        anyhow::ensure!(
+            files.len() <= MAX_FILES,
+            "Too many files, expected at most {MAX_FILES}"
+        );
+        for (path, content) in files {
+            check_path(path)?;
+            anyhow::ensure!(
+                content.len() <= MAX_FILE_SIZE,
+                "File `{path}` is larger than {MAX_FILE_SIZE} bytes"
+            );
+            anyhow::ensure!(
+                path != &code_file
+                    && path != &lang_cfg.source_path
+                    && !self.fixtures.contains_key(path),
+                "File `{path}` can't be overwritten"
+            );
+            if let Some(mutation) = &self.config.mutation {
+                anyhow::ensure!(
+                    path != &format!("{}.{}", mutation.module, lang.extension()),
+                    "File `{path}` can't be overwritten"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The code to run for `submission`: the student's edits put back between
+    /// the template's locked regions, so the scaffolding can't be changed.
+    pub fn source(&self, lang: &Language, submission: &Submission) -> anyhow::Result<String> {
+        let regions = &self
+            .lang_info
+            .get(lang)
+            .with_context(|| format!("Language `{}` not found for this question", lang.name()))?
+            .regions;
+
+        match (submission, regions.is_empty()) {
+            (Submission::Code(code), true) => Ok(code.to_string()),
+            (Submission::Code(_), false) => {
+                anyhow::bail!("Expected edits to the editable regions of the template")
+            }
+            (Submission::Edits(_), true) => {
+                anyhow::bail!("This template has no editable regions")
+            }
+            (Submission::Edits(edits), false) => {
+                let editable = regions.iter().filter(|r| r.editable).count();
+                anyhow::ensure!(
+                    edits.len() == editable,
+                    "Expected {editable} edits, found {}",
+                    edits.len()
+                );
+
+                let mut edits = edits.iter();
+                let mut out = String::new();
+                for region in regions {
+                    let code = match region.editable {
+                        true => edits.next().unwrap(),
+                        false => &region.code,
+                    };
+                    out += code;
+                    if !code.is_empty() && !code.ends_with('\n') {
+                        out.push('\n');
+                    }
+                }
+                anyhow::ensure!(
+                    out.len() <= MAX_FILE_SIZE,
+                    "Code is larger than {MAX_FILE_SIZE} bytes"
+                );
+                Ok(out)
+            }
+        }
+    }
+
+    /// Run a submission against every test case, linting it at the same time
+    /// when the exercise scores style
     pub fn run_tests(
+        &self,
+        lang: &Language,
+        submission: &Submission,
+        files: &HashMap<String, String>,
+        id: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<Evaluation> {
+        let content = &self.source(lang, submission)?;
+        anyhow::ensure!(
+            self.config.mutation.is_none(),
+            "Exercises with mutation testing are checked with `run_mutation`"
+        );
+        thread::scope(|s| {
+            let style = self
+                .config
+                .style
+                .as_ref()
+                .map(|style| s.spawn(|| style.lint(lang, cfg, content, files)));
+            let mut evaluation = self.evaluate(lang, content, files, id, cfg)?;
+            if let Some(style) = style {
+                evaluation.style = style.join().unwrap().context("While linting")?;
+            }
+            Ok(evaluation)
+        })
+    }
+
+    fn evaluate(
         &self,
         lang: &Language,
         content: &str,
+        files: &HashMap<String, String>,
         id: &str,
         cfg: &Config,
-    ) -> anyhow::Result<HashMap<String, TestResults>> {
-        #[derive(Debug, Deserialize)]
-        #[serde(tag = "type")]
-        enum TestOutput {
-            #[serde(rename = "answer")]
-            Answer {
-                value: serde_json::Value,
-                stdout: String,
-            },
-            #[serde(rename = "exception")]
-            Exception { traceback: String, stdout: String },
-        }
+    ) -> anyhow::Result<Evaluation> {
         let runner = &self
             .lang_info
             .get(lang)
-            .with_context(|| format!("Language `{}` not found for this question", lang.image()))?
+            .with_context(|| format!("Language `{}` not found for this question", lang.name()))?
             .runner;
-        let RunOutput { stdout, stderr, .. } = run(
-            cfg.docker.language_config.get(lang.image()).unwrap(),
-            &cfg.docker,
-            runner,
-            HashMap::from_iter([(id.to_string() + ".py", content.as_bytes())]),
-            "",
-        )
-        .context("While running generator")?;
-
-        let test_cases: HashMap<String, Vec<TestOutput>> = serde_json::from_str(&stdout)
-            .with_context(|| {
-                format!("While parsing test cases\nstdout:\n{stdout}\n\nstderr:\n{stderr}")
-            })?;
+        let results = if let Some(sql) = &self.config.sql {
+            Some(sql.run_tests(cfg, content, &self.fixtures)?)
+        } else if let Some(shell) = &self.config.shell {
+            Some(shell.run_tests(cfg, content, &self.fixtures)?)
+        } else {
+            None
+        };
+        if let Some(results) = results {
+            return Ok(Evaluation {
+                results,
+                coverage: None,
+                compile_error: None,
+                style: None,
+            });
+        }
+        self.check_files(lang, files, id, cfg)?;
+        let test_cases = match run_suite(lang, cfg, runner, id, content, files, &self.fixtures) {
+            Ok(test_cases) => test_cases,
+            Err(e) => {
+                return Ok(Evaluation {
+                    results: HashMap::new(),
+                    coverage: None,
+                    compile_error: Some(e.downcast::<CompileError>()?),
+                    style: None,
+                })
+            }
+        };
         let mut results = HashMap::new();
 
-        for (func, suite) in test_cases {
+        for (func, suite) in test_cases.functions {
             let fn_config = &self.config.functions[&func];
             let tests = &fn_config.tests;
             let (visible, hidden) = suite.as_slice().split_at(fn_config.visible_cases as usize);
@@ -160,20 +462,422 @@ impl Exercise {
                 }
                 TestOutput::Exception { .. } => false,
             });
+            let performance = fn_config.performance.as_ref().map(|perf| {
+                perf.check(
+                    test_cases
+                        .performance
+                        .get(&func)
+                        .map_or(&[], |t| t.as_slice()),
+                )
+            });
+            let passed =
+                visible_passed && hidden && performance.as_ref().is_none_or(|p| p.passed);
             results.insert(
                 func,
                 TestResults {
                     results: visible,
                     hidden,
+                    performance,
+                    passed,
+                },
+            );
+        }
+
+        for (class, suite) in test_cases.classes {
+            let class_config = self
+                .config
+                .classes
+                .get(&class)
+                .with_context(|| format!("ExcerciseConfig does not cotain class {class}"))?;
+            anyhow::ensure!(
+                suite.len() == class_config.tests.len(),
+                "Runner returned {} tests for class `{class}`, expected {}",
+                suite.len(),
+                class_config.tests.len()
+            );
+
+            let mut visible = Vec::new();
+            let mut visible_passed = true;
+            let mut hidden = true;
+            for (test, outputs) in class_config.tests.iter().zip(suite) {
+                let result = test.compare(&outputs);
+                let passed = matches!(result, TestResult::Correct { .. });
+                if test.hidden {
+                    hidden &= passed;
+                } else {
+                    visible_passed &= passed;
+                    visible.push(result);
+                }
+            }
+            results.insert(
+                class,
+                TestResults {
+                    results: visible,
+                    hidden,
+                    performance: None,
                     passed: visible_passed && hidden,
                 },
             );
         }
 
-        Ok(results)
+        Ok(Evaluation {
+            results,
+            coverage: test_cases.coverage,
+            compile_error: None,
+            style: None,
+        })
+    }
+
+    /// Run the tests a student wrote against the reference solution and every
+    /// mutant of it
+    pub fn run_mutation(
+        &self,
+        lang: &Language,
+        submission: &Submission,
+        files: &HashMap<String, String>,
+        id: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<MutationResults> {
+        let mutation = self
+            .config
+            .mutation
+            .as_ref()
+            .context("Exercise does not use mutation testing")?;
+        let solution = self
+            .solution
+            .as_ref()
+            .context("Exercise has no reference solution")?;
+        anyhow::ensure!(
+            &solution.lang == lang,
+            "Tests have to be written in {}",
+            solution.lang.display_name()
+        );
+        let tests = self.source(lang, submission)?;
+        self.check_files(lang, files, id, cfg)?;
+
+        let files = files
+            .iter()
+            .map(|(path, content)| (path.clone(), content.as_bytes()))
+            .chain(
+                self.fixtures
+                    .iter()
+                    .map(|(path, content)| (path.clone(), &content[..])),
+            )
+            .collect();
+        mutation.run(lang, cfg, &tests, &solution.code, &files)
+    }
+
+    /// Call `func` with `inputs` once, and the reference solution too if there
+    /// is one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_call(
+        &self,
+        lang: &Language,
+        submission: &Submission,
+        files: &HashMap<String, String>,
+        func: &str,
+        inputs: Vec<Value>,
+        id: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<TryResult> {
+        anyhow::ensure!(
+            self.lang_info.contains_key(lang),
+            "Language `{}` not found for this question",
+            lang.name()
+        );
+        let content = &self.source(lang, submission)?;
+        let fn_config = self
+            .config
+            .functions
+            .get(func)
+            .with_context(|| format!("Function `{func}` not found"))?;
+        anyhow::ensure!(
+            inputs.len() == fn_config.inputs.len(),
+            "Expected {} inputs, found {}",
+            fn_config.inputs.len(),
+            inputs.len()
+        );
+        for (i, (ty, input)) in fn_config.inputs.iter().zip(&inputs).enumerate() {
+            ty.check(input)
+                .with_context(|| format!("Input {i} is not a valid `{ty}`"))?;
+        }
+        self.check_files(lang, files, id, cfg)?;
+
+        let single = self.single_case(func, inputs);
+        let call = |lang: &Language, code_file: &str, content: &str, files| {
+            let runner = runner_template(lang, &single, code_file)
+                .context("While generating runner template")?;
+            run_suite(
+                lang,
+                cfg,
+                &runner,
+                code_file,
+                content,
+                files,
+                &self.fixtures,
+            )?
+            .functions
+            .remove(func)
+            .and_then(|mut suite| suite.pop())
+            .with_context(|| format!("Runner returned no output for `{func}`"))
+        };
+
+        let output = call(lang, id, content, files)?;
+        let expected = match &self.solution {
+            Some(solution) => Some(
+                call(&solution.lang, "solution", &solution.code, &solution.files)
+                    .context("While running reference solution")?,
+            ),
+            None => None,
+        };
+        Ok(TryResult { output, expected })
+    }
+
+    /// Record every line run while calling `func` with its `test`th visible
+    /// test case
+    #[allow(clippy::too_many_arguments)]
+    pub fn trace(
+        &self,
+        lang: &Language,
+        submission: &Submission,
+        files: &HashMap<String, String>,
+        func: &str,
+        test: usize,
+        id: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<TraceResult> {
+        anyhow::ensure!(
+            self.lang_info.contains_key(lang),
+            "Language `{}` not found for this question",
+            lang.name()
+        );
+        let content = &self.source(lang, submission)?;
+        let fn_config = self
+            .config
+            .functions
+            .get(func)
+            .with_context(|| format!("Function `{func}` not found"))?;
+        // hidden test cases can't be traced, that would give them away
+        let case = fn_config
+            .tests
+            .get(test)
+            .filter(|_| test < fn_config.visible_cases as usize)
+            .with_context(|| format!("Test case {test} not found"))?;
+        self.check_files(lang, files, id, cfg)?;
+
+        // leaving room in the output for the test case's own output
+        let runner = trace_template(
+            lang,
+            &self.single_case(func, case.inputs.clone()),
+            id,
+            cfg.docker.output_limit / 2,
+        )
+        .context("While generating runner template")?;
+        let mut suite = run_suite(lang, cfg, &runner, id, content, files, &self.fixtures)?;
+        let output = suite
+            .functions
+            .remove(func)
+            .and_then(|mut suite| suite.pop())
+            .with_context(|| format!("Runner returned no output for `{func}`"))?;
+        Ok(TraceResult {
+            trace: suite.trace.context("Runner returned no trace")?,
+            output,
+            expected: case.output.clone(),
+        })
+    }
+
+    /// Whether any function gives every student their own test cases
+    pub fn has_variants(&self) -> bool {
+        self.config.functions.values().any(|func| func.variants)
+    }
+
+    /// Generate `user`'s own visible test cases for every function with
+    /// variants, and solve them with the reference solution. `exercise` is the
+    /// full id of the exercise.
+    pub fn generate_variant(
+        &self,
+        exercise: &str,
+        user: &str,
+        cfg: &Config,
+    ) -> anyhow::Result<Variant> {
+        let solution = self
+            .solution
+            .as_ref()
+            .context("Exercise has no reference solution")?;
+
+        let mut config = ExerciseConfig {
+            functions: HashMap::new(),
+            classes: HashMap::new(),
+            coverage: false,
+            ..self.config.clone()
+        };
+        for (name, func) in self.config.functions.iter().filter(|(_, f)| f.variants) {
+            let mut func = FunctionConfig {
+                seed: variant_seed(exercise, user, name),
+                hidden_cases: 0,
+                performance: None,
+                ..func.clone()
+            };
+            func.tests = generate_cases(&func)
+                .with_context(|| format!("While generating test cases for function `{name}`"))?;
+            config.functions.insert(name.clone(), func);
+        }
+        solve(
+            &solution.lang,
+            cfg,
+            &solution.code,
+            &solution.files,
+            &self.fixtures,
+            &mut config,
+        )?;
+
+        Ok(config
+            .functions
+            .into_iter()
+            .map(|(name, func)| (name, func.tests))
+            .collect())
+    }
+
+    /// The exercise with the visible test cases of `variant` instead of the
+    /// shared ones. `id` is what the code is run as.
+    pub fn with_variant(&self, variant: Variant, id: &str) -> anyhow::Result<Exercise> {
+        let mut exercise = self.clone();
+        for (name, tests) in variant {
+            let func = exercise
+                .config
+                .functions
+                .get_mut(&name)
+                .filter(|func| func.variants)
+                .with_context(|| format!("Function `{name}` has no variants"))?;
+            let visible = func.visible_cases as usize;
+            anyhow::ensure!(
+                tests.len() == visible
+                    && func.tests.len() >= visible
+                    && tests.iter().all(|test| !test.hidden),
+                "Expected {visible} visible test cases for function `{name}`"
+            );
+            func.tests.splice(..visible, tests);
+        }
+
+        for (lang, info) in exercise.lang_info.iter_mut() {
+            if !info.runner.is_empty() {
+                info.runner = runner_template(lang, &exercise.config, id)
+                    .context("While generating runner template")?;
+            }
+        }
+        Ok(exercise)
+    }
+
+    /// The exercise with only `func`, and only one test case for it
+    fn single_case(&self, func: &str, inputs: Vec<Value>) -> ExerciseConfig {
+        let fn_config = &self.config.functions[func];
+        ExerciseConfig {
+            functions: HashMap::from_iter([(
+                func.to_string(),
+                FunctionConfig {
+                    tests: vec![TestCase {
+                        inputs,
+                        ..Default::default()
+                    }],
+                    performance: None,
+                    ..fn_config.clone()
+                },
+            )]),
+            classes: HashMap::new(),
+            coverage: false,
+            ..self.config.clone()
+        }
     }
 }
 
+/// The output of a runner template for a single test case
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum TestOutput {
+    #[serde(rename = "answer")]
+    Answer {
+        value: serde_json::Value,
+        stdout: String,
+    },
+    #[serde(rename = "exception")]
+    Exception { traceback: String, stdout: String },
+}
+
+/// What a runner template prints
+#[derive(Debug, Default, Deserialize)]
+pub struct SuiteOutput {
+    /// The output of every test case, by function
+    #[serde(default)]
+    pub functions: HashMap<String, Vec<TestOutput>>,
+    /// The output of every call in every test, by class. See
+    /// [`ClassTest::compare`](crate::class::ClassTest::compare).
+    #[serde(default)]
+    pub classes: HashMap<String, Vec<Vec<TestOutput>>>,
+    /// The execution trace, when rendered with [`trace_template`]
+    #[serde(default)]
+    pub trace: Option<Trace>,
+    /// The lines run by the visible test cases, when the exercise collects
+    /// coverage
+    #[serde(default)]
+    pub coverage: Option<Coverage>,
+    /// The outputs on the large inputs and how long they took, by function
+    #[serde(default)]
+    pub performance: HashMap<String, Vec<Timed>>,
+}
+
+/// Run the rendered `runner` against the code in `content`, which the runner
+/// imports as `code_file`. `files` and `fixtures` are put next to it. Code
+/// that doesn't compile fails with a [`CompileError`].
+fn run_suite(
+    lang: &Language,
+    cfg: &Config,
+    runner: &str,
+    code_file: &str,
+    content: &str,
+    files: &HashMap<String, String>,
+    fixtures: &HashMap<String, Vec<u8>>,
+) -> anyhow::Result<SuiteOutput> {
+    let source = format!("{code_file}.{}", lang.extension());
+    let RunOutput {
+        stdout,
+        stderr,
+        truncated,
+        compile_error,
+        ..
+    } = run(
+        cfg.docker.language_config.get(lang.name()).unwrap(),
+        &cfg.docker,
+        runner,
+        files
+            .iter()
+            .map(|(path, content)| (path.clone(), content.as_bytes()))
+            .chain(
+                fixtures
+                    .iter()
+                    .map(|(path, content)| (path.clone(), &content[..])),
+            )
+            .chain([(source.clone(), content.as_bytes())])
+            .collect(),
+        &RunOptions::default(),
+    )
+    .context("While running tests")?;
+    if compile_error {
+        let files = files.keys().map(|f| f.as_str()).collect::<Vec<_>>();
+        return Err(
+            CompileError::parse(lang.config().diagnostics, &stderr, &source, &files).into(),
+        );
+    }
+    anyhow::ensure!(
+        !truncated,
+        "Output exceeded the limit of {} bytes",
+        cfg.docker.output_limit
+    );
+
+    serde_json::from_str(&stdout).with_context(|| {
+        format!("While parsing test cases\nstdout:\n{stdout}\n\nstderr:\n{stderr}")
+    })
+}
+
 // impl Into<ExcerciseSerialize> for Exercise {
 //     fn into(self) -> ExcerciseSerialize {
 //         ExcerciseSerialize {
@@ -184,12 +888,41 @@ impl Exercise {
 //     }
 // }
 
+handlebars_helper!(json_helper: |value: Value| value.to_string());
+
 pub fn runner_template(lang: &Language, cfg: &ExerciseConfig, id: &str) -> anyhow::Result<String> {
+    render_runner(lang, cfg, id, Value::Null)
+}
+
+/// The runner template in trace mode, which records every line run in the
+/// student's code, keeping the trace to at most `max_bytes` of json
+pub fn trace_template(
+    lang: &Language,
+    cfg: &ExerciseConfig,
+    id: &str,
+    max_bytes: usize,
+) -> anyhow::Result<String> {
+    anyhow::ensure!(
+        lang.config().trace,
+        "{} does not support tracing",
+        lang.display_name()
+    );
+    render_runner(lang, cfg, id, trace_options(max_bytes))
+}
+
+fn render_runner(
+    lang: &Language,
+    cfg: &ExerciseConfig,
+    id: &str,
+    trace: Value,
+) -> anyhow::Result<String> {
     let mut handlebars = Handlebars::new();
+    // inputs can be anything, so they're passed along as json
+    handlebars.register_helper("json", Box::new(json_helper));
     handlebars
         .register_template_file(
             "runner",
-            &path::LANGUAGES.join(lang.image()).join("runner.hbs"),
+            &path::LANGUAGES.join(lang.name()).join("runner.hbs"),
         )
         .context("While registering template file")?;
     handlebars.register_escape_fn(handlebars::no_escape);
@@ -200,15 +933,28 @@ pub fn runner_template(lang: &Language, cfg: &ExerciseConfig, id: &str) -> anyho
             &json!({
                 "code_file": id,
                 "functions": &cfg.functions,
+                "classes": cfg
+                    .classes
+                    .iter()
+                    .map(|(name, class)| (name.clone(), class.runner_tests()))
+                    .collect::<HashMap<_, _>>(),
+                "trace": trace,
+                "coverage": cfg.coverage && lang.config().coverage,
+                "performance": cfg
+                    .functions
+                    .iter()
+                    .filter_map(|(name, func)| Some((name, func.performance.as_ref()?.runner_data())))
+                    .collect::<HashMap<_, _>>(),
             }),
         )
         .context("While rendering template file")?;
     Ok(out)
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TestCase {
     pub inputs: Vec<serde_json::Value>,
+    #[serde(default)]
     pub output: serde_json::Value,
     #[serde(default)]
     pub hidden: bool,
@@ -231,7 +977,7 @@ pub fn generator_template(
     handlebars
         .register_template_file(
             "generator",
-            &path::LANGUAGES.join(lang.image()).join("generator.hbs"),
+            &path::LANGUAGES.join(lang.name()).join("generator.hbs"),
         )
         .context("While registerring template file")?;
     handlebars.register_escape_fn(handlebars::no_escape);
@@ -251,14 +997,18 @@ pub fn generate(
     lang: &Language,
     cfg: &Config,
     content: &str,
+    fixtures: &HashMap<String, Vec<u8>>,
     exercise_cfg: &mut ExerciseConfig,
 ) -> anyhow::Result<()> {
     let RunOutput { stdout, stderr, .. } = run(
-        cfg.docker.language_config.get(lang.image()).unwrap(),
+        cfg.docker.language_config.get(lang.name()).unwrap(),
         &cfg.docker,
         &generator_template(lang, content, exercise_cfg).expect("While generating template"),
-        HashMap::new(),
-        "",
+        fixtures
+            .iter()
+            .map(|(path, content)| (path.clone(), &content[..]))
+            .collect(),
+        &RunOptions::default(),
     )
     .context("While running generator")?;
 
@@ -277,7 +1027,7 @@ pub fn generate(
             "Test cases for function `{}` < cfg.hidden_cases + cfg.visible_cases",
             func
         );
-        for test in tests.iter_mut().skip(cfg.hidden_cases as usize) {
+        for test in tests.iter_mut().skip(cfg.visible_cases as usize) {
             test.hidden = true
         }
         cfg.tests = tests;
@@ -286,15 +1036,126 @@ pub fn generate(
     Ok(())
 }
 
+/// Run the reference `solution` on the inputs of every test case, and use its
+/// answers as the expected outputs.
+pub fn solve(
+    lang: &Language,
+    cfg: &Config,
+    solution: &str,
+    files: &HashMap<String, String>,
+    fixtures: &HashMap<String, Vec<u8>>,
+    exercise_cfg: &mut ExerciseConfig,
+) -> anyhow::Result<()> {
+    let runner = runner_template(lang, exercise_cfg, "solution")
+        .context("While generating runner template")?;
+    let outputs = run_suite(lang, cfg, &runner, "solution", solution, files, fixtures)
+        .context("While running reference solution")?;
+
+    for (func, suite) in outputs.functions {
+        let fn_config = exercise_cfg
+            .functions
+            .get_mut(&func)
+            .with_context(|| format!("ExcerciseConfig does not cotain function {func}"))?;
+        anyhow::ensure!(
+            suite.len() == fn_config.tests.len(),
+            "Reference solution returned {} answers for function `{func}`, expected {}",
+            suite.len(),
+            fn_config.tests.len()
+        );
+
+        for (test, output) in fn_config.tests.iter_mut().zip(suite) {
+            match output {
+                TestOutput::Answer { value, .. } => test.output = value,
+                TestOutput::Exception { traceback, .. } => anyhow::bail!(
+                    "Reference solution raised an exception on `{func}({})`:\n{traceback}",
+                    test.inputs
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+    }
+
+    for (class, suite) in outputs.classes {
+        let class_config = exercise_cfg
+            .classes
+            .get_mut(&class)
+            .with_context(|| format!("ExcerciseConfig does not cotain class {class}"))?;
+        anyhow::ensure!(
+            suite.len() == class_config.tests.len(),
+            "Reference solution returned {} tests for class `{class}`, expected {}",
+            suite.len(),
+            class_config.tests.len()
+        );
+
+        for (i, (test, outputs)) in class_config.tests.iter_mut().zip(suite).enumerate() {
+            // the first output is the constructor's, and the runner stops at the
+            // first exception
+            if let Some(TestOutput::Exception { traceback, .. }) = outputs.last() {
+                anyhow::bail!(
+                    "Reference solution raised an exception on test {i} of class \
+                     `{class}`:\n{traceback}"
+                );
+            }
+            anyhow::ensure!(
+                outputs.len() == test.calls.len() + 1,
+                "Reference solution returned {} outputs for test {i} of class `{class}`, \
+                 expected {}",
+                outputs.len(),
+                test.calls.len() + 1
+            );
+            for (call, output) in test.calls.iter_mut().zip(outputs.into_iter().skip(1)) {
+                if let TestOutput::Answer { value, .. } = output {
+                    call.output = value;
+                }
+            }
+        }
+    }
+
+    for (func, timed) in outputs.performance {
+        let perf = exercise_cfg
+            .functions
+            .get_mut(&func)
+            .and_then(|f| f.performance.as_mut())
+            .with_context(|| format!("Function `{func}` has no performance tests"))?;
+        anyhow::ensure!(
+            timed.len() == perf.tests.len(),
+            "Reference solution returned {} answers for the large inputs of `{func}`, expected {}",
+            timed.len(),
+            perf.tests.len()
+        );
+
+        for (test, t) in perf.tests.iter_mut().zip(&timed) {
+            match &t.output {
+                TestOutput::Answer { value, .. } => test.output = value.clone(),
+                TestOutput::Exception { traceback, .. } => anyhow::bail!(
+                    "Reference solution raised an exception on a large input of `{func}`:\n\
+                     {traceback}"
+                ),
+            }
+        }
+        perf.reference = Some(timed.iter().map(|t| t.time).sum());
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use amplitude_common::config_and_set_path;
 
     use super::*;
+    use crate::constraint::generate_cases;
+
+    fn python() -> Language {
+        "python".parse().unwrap()
+    }
 
     #[test]
     fn test_simple_langs() {
-        test_simple(&Language::Python, "def test(x):\n    return x - 1\n").unwrap();
+        test_simple(&python(), "def test(x):\n    return x - 1\n").unwrap();
     }
 
     fn test_simple(lang: &Language, code: &str) -> anyhow::Result<()> {
@@ -303,14 +1164,23 @@ mod test {
         let config = ExerciseConfig {
             title: "test".to_string(),
             instructions: "test".to_string(),
+            sql: None,
+            shell: None,
+            mutation: None,
+            coverage: false,
+            style: None,
+            classes: HashMap::new(),
             functions: HashMap::from_iter([(
                 "test".to_string(),
                 FunctionConfig {
                     inputs: vec![VariableType::Int],
                     output: VariableType::Int,
+                    constraints: vec![],
                     seed: 0,
                     hidden_cases: 2,
                     visible_cases: 2,
+                    variants: false,
+                    performance: None,
                     tests: vec![
                         TestCase {
                             inputs: vec![json!(1)],
@@ -343,13 +1213,19 @@ mod test {
                 LanguageInfo {
                     runner: runner_template(lang, &config, "test").unwrap(),
                     code: String::new(),
+                    files: HashMap::new(),
+                    available: true,
+                    regions: Vec::new(),
                 },
             )]),
             config,
+            fixtures: HashMap::new(),
+            solution: None,
         };
-        let result = exercise.run_tests(lang, code, "test", &cfg)?;
+        let result =
+            exercise.run_tests(lang, &Submission::Code(code), &HashMap::new(), "test", &cfg)?;
         anyhow::ensure!(
-            result["test"]
+            result.results["test"]
                 == TestResults {
                     results: vec![
                         TestResult::Correct {
@@ -360,12 +1236,112 @@ mod test {
                         }
                     ],
                     hidden: true,
+                    performance: None,
                     passed: true
                 }
         );
         Ok(())
     }
 
+    #[test]
+    fn test_check_files() {
+        let cfg = config_and_set_path().unwrap();
+        let exercise = Exercise::new(
+            ExerciseConfig {
+                title: "test".to_string(),
+                instructions: "test".to_string(),
+                sql: None,
+                shell: None,
+                mutation: None,
+                coverage: false,
+                style: None,
+                classes: HashMap::new(),
+                functions: HashMap::new(),
+            },
+            HashMap::new(),
+            HashMap::from_iter([("data.csv".to_string(), b"1,2,3".to_vec())]),
+        );
+        let check = |files: &[(&str, &str)]| {
+            let files = files
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            exercise.check_files(&python(), &files, "test", &cfg)
+        };
+
+        assert!(check(&[("helpers.py", ""), ("data/input.txt", "")]).is_ok());
+        assert!(check(&[("test.py", "")]).is_err());
+        assert!(check(&[("main.py", "")]).is_err());
+        assert!(check(&[("data.csv", "")]).is_err());
+        assert!(check(&[("../helpers.py", "")]).is_err());
+        assert!(check(&[("big.txt", &"a".repeat(MAX_FILE_SIZE + 1))]).is_err());
+    }
+
+    #[test]
+    fn test_split_regions() {
+        let region = |code: &str, editable| Region {
+            code: code.to_string(),
+            editable,
+        };
+
+        assert_eq!(split_regions("def f():\n    pass\n", "# ---"), vec![]);
+        assert_eq!(
+            split_regions("def f(x):\n# ---\n    pass\n  # ---  \nf(1)\n", "# ---"),
+            vec![
+                region("def f(x):\n", false),
+                region("    pass\n", true),
+                region("f(1)\n", false),
+            ]
+        );
+        assert_eq!(
+            split_regions("# ---\nx = 1", "# ---"),
+            vec![region("", false), region("x = 1", true)]
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let code = "def f(x):\n# ---\n    pass\n# ---\nprint(f(1))\n";
+        let exercise = Exercise::new(
+            ExerciseConfig {
+                title: "test".to_string(),
+                instructions: "test".to_string(),
+                sql: None,
+                shell: None,
+                mutation: None,
+                coverage: false,
+                style: None,
+                classes: HashMap::new(),
+                functions: HashMap::new(),
+            },
+            HashMap::from_iter([(
+                python(),
+                LanguageInfo {
+                    runner: String::new(),
+                    code: String::new(),
+                    files: HashMap::new(),
+                    available: true,
+                    regions: split_regions(code, "# ---"),
+                },
+            )]),
+            HashMap::new(),
+        );
+        let edits = |edits: &[&str]| {
+            let edits = edits.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+            exercise.source(&python(), &Submission::Edits(&edits))
+        };
+
+        assert_eq!(
+            edits(&["    return x"]).unwrap(),
+            "def f(x):\n    return x\nprint(f(1))\n"
+        );
+        assert!(edits(&[]).is_err());
+        assert!(edits(&["    return x", "print(2)"]).is_err());
+        assert!(exercise
+            .source(&python(), &Submission::Code("print(2)"))
+            .is_err());
+    }
+
     #[test]
     fn test_generate() {
         let config = config_and_set_path().unwrap();
@@ -373,32 +1349,42 @@ mod test {
         let mut exercise_cfg = ExerciseConfig {
             title: "test".to_string(),
             instructions: "test".to_string(),
+            sql: None,
+            shell: None,
+            mutation: None,
+            coverage: false,
+            style: None,
+            classes: HashMap::new(),
             functions: HashMap::from_iter([(
                 "test".to_string(),
                 FunctionConfig {
                     inputs: vec![VariableType::Int],
                     output: VariableType::Int,
+                    constraints: vec![],
                     seed: 0,
-                    hidden_cases: 2,
+                    hidden_cases: 3,
                     visible_cases: 2,
+                    variants: false,
+                    performance: None,
                     tests: vec![],
                 },
             )]),
         };
 
         generate(
-            &Language::Python,
+            &python(),
             &config,
             "def gen_test(ctx):\n    ctx.inputs([1])\n    ctx.output(1)\n",
+            &HashMap::new(),
             &mut exercise_cfg,
         )
         .expect("Errors in generation");
 
         let tests = &exercise_cfg.functions["test"].tests;
+        assert_eq!(tests.len(), 5);
         assert!(!tests[0].hidden);
         assert!(!tests[1].hidden);
-        assert!(tests[2].hidden);
-        assert!(tests[3].hidden);
+        assert!(tests[2..].iter().all(|t| t.hidden));
 
         for test in tests {
             assert!(test.inputs.len() == 1);
@@ -406,4 +1392,479 @@ mod test {
             assert!(test.output == 1);
         }
     }
+
+    #[test]
+    fn test_solve() {
+        let config = config_and_set_path().unwrap();
+
+        let mut func = FunctionConfig {
+            inputs: vec![VariableType::Int],
+            output: VariableType::Int,
+            constraints: vec![],
+            seed: 0,
+            hidden_cases: 2,
+            visible_cases: 2,
+            variants: false,
+            performance: None,
+            tests: vec![],
+        };
+        func.tests = generate_cases(&func).unwrap();
+        let mut exercise_cfg = ExerciseConfig {
+            title: "test".to_string(),
+            instructions: "test".to_string(),
+            sql: None,
+            shell: None,
+            mutation: None,
+            coverage: false,
+            style: None,
+            classes: HashMap::new(),
+            functions: HashMap::from_iter([("test".to_string(), func)]),
+        };
+
+        solve(
+            &python(),
+            &config,
+            "def test(x):\n    return x * 2\n",
+            &HashMap::new(),
+            &HashMap::new(),
+            &mut exercise_cfg,
+        )
+        .expect("Errors in reference solution");
+
+        for test in &exercise_cfg.functions["test"].tests {
+            assert_eq!(test.output, test.inputs[0].as_i64().unwrap() * 2);
+        }
+    }
+
+    #[test]
+    fn test_performance() {
+        let config = config_and_set_path().unwrap();
+
+        // the outputs on these are far bigger than the output limit
+        let mut exercise_cfg: ExerciseConfig = toml::from_str(
+            r#"
+            title = "test"
+
+            [functions.sort]
+            inputs = ["int[]"]
+            output = "int[]"
+            constraints = [{ len = [1, 5] }]
+
+            [functions.sort.performance]
+            constraints = [{ len = [50000, 50000], items = { min = 0, max = 1000000 } }]
+            factor = 100.0
+            "#,
+        )
+        .unwrap();
+        let func = exercise_cfg.functions.get_mut("sort").unwrap();
+        func.tests = generate_cases(func).unwrap();
+        let perf = func.performance.clone().unwrap();
+        func.performance.as_mut().unwrap().tests = perf.generate_cases(func).unwrap();
+
+        let sort = "def sort(xs):\n    return sorted(xs)\n";
+        solve(
+            &python(),
+            &config,
+            sort,
+            &HashMap::new(),
+            &HashMap::new(),
+            &mut exercise_cfg,
+        )
+        .expect("Errors in reference solution");
+        assert!(exercise_cfg.functions["sort"]
+            .performance
+            .as_ref()
+            .unwrap()
+            .reference
+            .is_some());
+
+        let lang_info = HashMap::from_iter([(
+            python(),
+            LanguageInfo {
+                runner: runner_template(&python(), &exercise_cfg, "test").unwrap(),
+                code: String::new(),
+                files: HashMap::new(),
+                available: true,
+                regions: Vec::new(),
+            },
+        )]);
+        let exercise = Exercise::new(exercise_cfg, lang_info, HashMap::new());
+        let run = |code: &str| {
+            exercise
+                .run_tests(
+                    &python(),
+                    &Submission::Code(code),
+                    &HashMap::new(),
+                    "test",
+                    &config,
+                )
+                .unwrap()
+                .results
+                .remove("sort")
+                .unwrap()
+                .performance
+                .unwrap()
+        };
+
+        assert!(run(sort).passed);
+        assert!(!run("def sort(xs):\n    return sorted(xs)[1:]\n").passed);
+    }
+
+    #[test]
+    fn test_classes() {
+        let config = config_and_set_path().unwrap();
+
+        let mut exercise_cfg: ExerciseConfig = toml::from_str(
+            r#"
+            title = "test"
+
+            [classes.Stack.methods]
+            push = { inputs = ["int"] }
+            pop = { inputs = [], output = "int" }
+
+            [[classes.Stack.tests]]
+            calls = [
+                { method = "push", inputs = [1] },
+                { method = "push", inputs = [2] },
+                { method = "pop" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let stack = "class Stack:\n    def __init__(self):\n        self.items = []\n    \
+                     def push(self, x):\n        self.items.append(x)\n    \
+                     def pop(self):\n        return self.items.pop()\n";
+        solve(
+            &python(),
+            &config,
+            stack,
+            &HashMap::new(),
+            &HashMap::new(),
+            &mut exercise_cfg,
+        )
+        .expect("Errors in reference solution");
+        assert_eq!(exercise_cfg.classes["Stack"].tests[0].calls[2].output, 2);
+
+        let lang_info = HashMap::from_iter([(
+            python(),
+            LanguageInfo {
+                runner: runner_template(&python(), &exercise_cfg, "test").unwrap(),
+                code: String::new(),
+                files: HashMap::new(),
+                available: true,
+                regions: Vec::new(),
+            },
+        )]);
+        let exercise = Exercise::new(exercise_cfg, lang_info, HashMap::new());
+        let run = |code: &str| {
+            exercise
+                .run_tests(
+                    &python(),
+                    &Submission::Code(code),
+                    &HashMap::new(),
+                    "test",
+                    &config,
+                )
+                .unwrap()
+                .results
+                .remove("Stack")
+                .unwrap()
+        };
+
+        assert!(run(stack).passed);
+        let queue = stack.replace("self.items.pop()", "self.items.pop(0)");
+        assert!(matches!(
+            run(&queue).results[0],
+            TestResult::Diverged { call: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_fixtures() {
+        let config = config_and_set_path().unwrap();
+
+        let mut exercise_cfg = ExerciseConfig {
+            title: "test".to_string(),
+            instructions: "test".to_string(),
+            sql: None,
+            shell: None,
+            mutation: None,
+            coverage: false,
+            style: None,
+            classes: HashMap::new(),
+            functions: HashMap::from_iter([(
+                "test".to_string(),
+                FunctionConfig {
+                    inputs: vec![VariableType::Int],
+                    output: VariableType::Int,
+                    constraints: vec![],
+                    seed: 0,
+                    hidden_cases: 1,
+                    visible_cases: 1,
+                    variants: false,
+                    performance: None,
+                    tests: vec![],
+                },
+            )]),
+        };
+
+        generate(
+            &python(),
+            &config,
+            "def gen_test(ctx):\n    n = int(open('data/n.txt').read())\n    ctx.inputs([n])\n    ctx.output(n)\n",
+            &HashMap::from_iter([("data/n.txt".to_string(), b"7".to_vec())]),
+            &mut exercise_cfg,
+        )
+        .expect("Errors in generation");
+
+        for test in &exercise_cfg.functions["test"].tests {
+            assert_eq!(test.inputs[0], 7);
+        }
+    }
+
+    fn try_exercise() -> Exercise {
+        let func = FunctionConfig {
+            inputs: vec![
+                VariableType::String,
+                VariableType::Array(Box::new(VariableType::Int)),
+            ],
+            output: VariableType::String,
+            constraints: vec![],
+            seed: 0,
+            hidden_cases: 0,
+            visible_cases: 0,
+            variants: false,
+            performance: None,
+            tests: vec![],
+        };
+        let config = ExerciseConfig {
+            title: "test".to_string(),
+            instructions: "test".to_string(),
+            sql: None,
+            shell: None,
+            mutation: None,
+            coverage: false,
+            style: None,
+            classes: HashMap::new(),
+            functions: HashMap::from_iter([("test".to_string(), func)]),
+        };
+        let lang_info = HashMap::from_iter([(
+            python(),
+            LanguageInfo {
+                runner: runner_template(&python(), &config, "test").unwrap(),
+                code: String::new(),
+                files: HashMap::new(),
+                available: true,
+                regions: Vec::new(),
+            },
+        )]);
+        let mut exercise = Exercise::new(config, lang_info, HashMap::new());
+        exercise.solution = Some(Solution {
+            lang: python(),
+            code: "def test(s, n):\n    return s * sum(n)\n".to_string(),
+            files: HashMap::new(),
+        });
+        exercise
+    }
+
+    #[test]
+    fn test_runner_template_inputs() {
+        config_and_set_path().unwrap();
+        let mut exercise = try_exercise();
+        let func = exercise.config.functions.get_mut("test").unwrap();
+        func.tests = vec![TestCase {
+            inputs: vec![json!("a\"b"), json!([1, 2])],
+            ..Default::default()
+        }];
+        let runner = runner_template(&python(), &exercise.config, "test").unwrap();
+        assert!(runner.contains(r#""inputs": ["a\"b",[1,2]],"#));
+    }
+
+    #[test]
+    fn test_try_call() {
+        let cfg = config_and_set_path().unwrap();
+        let exercise = try_exercise();
+        let code = "def test(s, n):\n    return s * len(n)\n";
+        let call = |inputs| {
+            exercise.try_call(
+                &python(),
+                &Submission::Code(code),
+                &HashMap::new(),
+                "test",
+                inputs,
+                "test",
+                &cfg,
+            )
+        };
+
+        let result = call(vec![json!("ab"), json!([1, 2])]).unwrap();
+        assert!(matches!(result.output, TestOutput::Answer { value, .. } if value == "abab"));
+        assert!(
+            matches!(result.expected, Some(TestOutput::Answer { value, .. }) if value == "ababab")
+        );
+
+        assert!(call(vec![json!("ab")]).is_err());
+        assert!(call(vec![json!(1), json!([1, 2])]).is_err());
+    }
+
+    #[test]
+    fn test_variant() {
+        let cfg = config_and_set_path().unwrap();
+        let mut exercise = try_exercise();
+        let func = exercise.config.functions.get_mut("test").unwrap();
+        func.variants = true;
+        func.visible_cases = 2;
+        func.hidden_cases = 1;
+        func.tests = generate_cases(func).unwrap();
+        let hidden = func.tests[2].clone();
+
+        let variant = exercise.generate_variant("a/test", "user", &cfg).unwrap();
+        assert_eq!(variant["test"].len(), 2);
+        assert_eq!(
+            variant,
+            exercise.generate_variant("a/test", "user", &cfg).unwrap()
+        );
+        assert_ne!(
+            variant,
+            exercise.generate_variant("a/test", "other", &cfg).unwrap()
+        );
+
+        let own = exercise.with_variant(variant.clone(), "test").unwrap();
+        let tests = &own.config.functions["test"].tests;
+        assert_eq!(tests[..2], variant["test"][..]);
+        assert_eq!(tests[2], hidden);
+        let solution = exercise.solution.as_ref().unwrap();
+        assert!(own
+            .run_tests(
+                &python(),
+                &Submission::Code(&solution.code),
+                &HashMap::new(),
+                "test",
+                &cfg,
+            )
+            .unwrap()
+            .passed());
+
+        let mut wrong = variant;
+        wrong.get_mut("test").unwrap().pop();
+        assert!(exercise.with_variant(wrong, "test").is_err());
+    }
+
+    #[test]
+    fn test_coverage() {
+        let cfg = config_and_set_path().unwrap();
+        let mut exercise = try_exercise();
+        exercise.config.coverage = true;
+        let func = exercise.config.functions.get_mut("test").unwrap();
+        func.visible_cases = 1;
+        func.tests = vec![
+            TestCase {
+                inputs: vec![json!("ab"), json!([1])],
+                output: json!("ab"),
+                hidden: false,
+            },
+            TestCase {
+                inputs: vec![json!("ab"), json!([])],
+                output: json!(""),
+                hidden: true,
+            },
+        ];
+        exercise.lang_info.get_mut(&python()).unwrap().runner =
+            runner_template(&python(), &exercise.config, "test").unwrap();
+
+        let code = "def test(s, n):\n    if not n:\n        return ''\n    return s\n";
+        let evaluation = exercise
+            .run_tests(
+                &python(),
+                &Submission::Code(code),
+                &HashMap::new(),
+                "test",
+                &cfg,
+            )
+            .unwrap();
+        assert!(evaluation.passed());
+        assert_eq!(
+            evaluation.coverage,
+            Some(Coverage {
+                covered: BTreeSet::from([2, 4]),
+                uncovered: BTreeSet::from([3]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_trace_template() {
+        config_and_set_path().unwrap();
+        let exercise = try_exercise();
+        let runner = &exercise.lang_info[&python()].runner;
+        let traced = trace_template(&python(), &exercise.config, "test", 1024).unwrap();
+
+        assert!(!runner.contains("sys.settrace(tracer)"));
+        assert!(traced.contains("sys.settrace(tracer)"));
+        assert!(traced.contains("> 1024"));
+    }
+
+    #[test]
+    fn test_trace() {
+        let cfg = config_and_set_path().unwrap();
+        let mut exercise = try_exercise();
+        let func = exercise.config.functions.get_mut("test").unwrap();
+        func.visible_cases = 1;
+        func.tests = vec![
+            TestCase {
+                inputs: vec![json!("ab"), json!([1, 2, 3])],
+                output: json!("ababab"),
+                hidden: false,
+            },
+            TestCase {
+                inputs: vec![json!("a"), json!([])],
+                output: json!(""),
+                hidden: true,
+            },
+        ];
+        let trace = |code, test| {
+            exercise.trace(
+                &python(),
+                &Submission::Code(code),
+                &HashMap::new(),
+                "test",
+                test,
+                "test",
+                &cfg,
+            )
+        };
+
+        let code = "def test(s, n):\n    out = ''\n    for _ in n[1:]:\n        out += s\n    print(out)\n    return out\n";
+        let result = trace(code, 0).unwrap();
+        assert!(!result.trace.truncated);
+        assert_eq!(result.expected, json!("ababab"));
+        assert!(matches!(result.output, TestOutput::Answer { value, .. } if value == "abab"));
+        let first = &result.trace.events[0];
+        assert_eq!(first.line, 2);
+        assert_eq!(first.stack, vec!["test"]);
+        assert_eq!(first.locals["s"], json!("ab"));
+        let last = result.trace.events.last().unwrap();
+        assert_eq!(last.line, 6);
+        assert_eq!(last.locals["out"], json!("abab"));
+        assert_eq!(last.stdout, "abab\n");
+
+        // each step has the locals as they were then
+        let code =
+            "def test(s, n):\n    out = []\n    out.append(1)\n    out.append(2)\n    return s\n";
+        let result = trace(code, 0).unwrap();
+        let locals = result
+            .trace
+            .events
+            .iter()
+            .filter_map(|event| event.locals.get("out"))
+            .collect::<Vec<_>>();
+        assert_eq!(locals, [&json!([]), &json!([1]), &json!([1, 2])]);
+
+        let code = "def test(s, n):\n    for _ in range(10 ** 5):\n        pass\n    return s\n";
+        let result = trace(code, 0).unwrap();
+        assert!(result.trace.truncated);
+        assert!(result.trace.events.len() <= crate::trace::MAX_TRACE_EVENTS);
+
+        assert!(trace(code, 1).is_err());
+    }
 }
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use amplitude_common::config::{DockerConfig, LanguageConfig};
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::runner::{run, RunOptions, RunOutput};
+
+/// What came of running a language's formatter on some code
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Formatted {
+    Code {
+        code: String,
+    },
+    /// The formatter couldn't parse the code
+    Error {
+        message: String,
+    },
+}
+
+/// Format `code` with the language's formatter
+pub fn format_code(
+    lang: &LanguageConfig,
+    cfg: &DockerConfig,
+    code: &str,
+) -> anyhow::Result<Formatted> {
+    let formatter = lang
+        .formatter
+        .as_ref()
+        .with_context(|| format!("{} has no formatter", lang.display_name))?;
+    let RunOutput {
+        stdout,
+        stderr,
+        exit_code,
+        truncated,
+        ..
+    } = run(
+        lang,
+        cfg,
+        code,
+        HashMap::new(),
+        &RunOptions {
+            command: Some(formatter),
+            ..Default::default()
+        },
+    )
+    .context("While running formatter")?;
+    anyhow::ensure!(
+        !truncated,
+        "Formatter output exceeded the limit of {} bytes",
+        cfg.output_limit
+    );
+
+    Ok(match exit_code {
+        0 => Formatted::Code { code: stdout },
+        _ => Formatted::Error { message: stderr },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    #[test]
+    fn test_format_code() {
+        let cfg = config_and_set_path().unwrap();
+        let lang = cfg.docker.language_config.get("python").unwrap();
+
+        let formatted = format_code(lang, &cfg.docker, "x=[1,\n  2]\nprint( x )\n").unwrap();
+        assert_eq!(
+            formatted,
+            Formatted::Code {
+                code: "x = [1, 2]\nprint(x)\n".to_string()
+            }
+        );
+
+        let formatted = format_code(lang, &cfg.docker, "def f(:\n").unwrap();
+        assert!(matches!(formatted, Formatted::Error { .. }));
+    }
+}
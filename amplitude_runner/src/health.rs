@@ -0,0 +1,88 @@
+use std::{collections::HashMap, fs, thread};
+
+use amplitude_common::{config::DockerConfig, path};
+use anyhow::Context;
+
+use crate::{
+    images::{image_status, ImageStatus},
+    runner::{run, RunOptions, RunOutput},
+};
+
+/// What `hello.<ext>` is expected to print
+const HELLO_WORLD: &str = "Hello, World!";
+
+/// Check that code can actually be run in `lang`: its image is built, it has a
+/// runner template unless its exercises are checked without one, and
+/// `hello.<ext>` prints what it should.
+///
+/// Returns the status of the image, which may be outdated but still usable.
+pub fn check_language(cfg: &DockerConfig, lang: &str) -> anyhow::Result<ImageStatus> {
+    let lang_cfg = cfg
+        .language_config
+        .get(lang)
+        .with_context(|| format!("Language `{lang}` not found in `languages.toml`"))?;
+    let dir = path::LANGUAGES.join(lang);
+
+    let status = image_status(&cfg.command, lang, lang_cfg)?;
+    anyhow::ensure!(
+        status != ImageStatus::Missing,
+        "Image `{}` not found, try running `cargo r -p amplitude_runner -- build {lang}`",
+        lang_cfg.image_name
+    );
+    anyhow::ensure!(
+        !lang_cfg.runner_template || dir.join("runner.hbs").exists(),
+        "Runner template `{}` not found",
+        dir.join("runner.hbs").display()
+    );
+
+    let hello = dir.join(format!("hello.{}", lang_cfg.extension));
+    let src = fs::read_to_string(&hello)
+        .with_context(|| format!("While reading `{}`", hello.display()))?;
+    let RunOutput {
+        stdout,
+        stderr,
+        exit_code,
+        ..
+    } = run(lang_cfg, cfg, &src, HashMap::new(), &RunOptions::default())
+        .context("While running hello world")?;
+    anyhow::ensure!(
+        exit_code == 0 && stdout.trim() == HELLO_WORLD,
+        "Hello world exited with code {exit_code}\nstdout:\n{stdout}\n\nstderr:\n{stderr}"
+    );
+
+    Ok(status)
+}
+
+/// Check every language in `languages.toml` at once
+pub fn check_languages(cfg: &DockerConfig) -> HashMap<String, anyhow::Result<ImageStatus>> {
+    thread::scope(|s| {
+        let handles = cfg
+            .language_config
+            .keys()
+            .map(|lang| (lang, s.spawn(|| check_language(cfg, lang))))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|(lang, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Panicked while checking `{lang}`")));
+                (lang.clone(), result)
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    #[test]
+    fn test_check_python() {
+        let cfg = config_and_set_path().unwrap();
+        check_language(&cfg.docker, "python").unwrap();
+        assert!(check_language(&cfg.docker, "brainfuck").is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Component, Path},
+    process::{Command, Stdio},
+};
+
+use amplitude_common::{config::LanguageConfig, path};
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+
+/// The label on an image that stores the hash of the files it was built from
+pub const HASH_LABEL: &str = "amplitude.hash";
+
+/// The paths in `languages/<lang>` that an image is built from: the
+/// `Dockerfile` and everything it `ADD`s or `COPY`s in, which can be
+/// directories
+fn image_files(dockerfile: &str) -> Vec<String> {
+    let mut files = vec!["Dockerfile".to_string()];
+    for line in dockerfile.lines() {
+        let line = line.trim_start();
+        let (instruction, mut rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if !instruction.eq_ignore_ascii_case("ADD") && !instruction.eq_ignore_ascii_case("COPY") {
+            continue;
+        }
+        rest = rest.trim_start();
+        while rest.starts_with("--") {
+            rest = rest
+                .split_once(char::is_whitespace)
+                .map_or("", |(_, rest)| rest.trim_start());
+        }
+        // either `COPY a b /dst/` or `COPY ["a", "b", "/dst/"]`
+        let args = match rest.starts_with('[') {
+            true => serde_json::from_str::<Vec<String>>(rest).unwrap_or_default(),
+            false => rest.split_whitespace().map(str::to_string).collect(),
+        };
+        // the last argument is where they're put in the image
+        if let Some((_, sources)) = args.split_last() {
+            files.extend(
+                sources
+                    .iter()
+                    .filter(|s| !s.contains("://"))
+                    .map(|s| s.to_string()),
+            );
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// The files at `path` in `dir`, being every file under it for a directory,
+/// relative to `dir` and sorted
+fn files_in(dir: &Path, path: &str) -> anyhow::Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.join(path)];
+    while let Some(path) = stack.pop() {
+        if path.is_dir() {
+            for entry in fs::read_dir(&path)
+                .with_context(|| format!("While reading `{}`", path.display()))?
+            {
+                stack.push(entry?.path());
+            }
+        } else {
+            let relative = path.strip_prefix(dir).unwrap_or(&path);
+            files.push(
+                relative
+                    .components()
+                    .filter(|c| !matches!(c, Component::CurDir))
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            );
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageStatus {
+    /// The image has never been built
+    Missing,
+    /// The image was built from files that have changed since
+    Outdated,
+    UpToDate,
+}
+
+impl Display for ImageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ImageStatus::Missing => "missing",
+            ImageStatus::Outdated => "outdated",
+            ImageStatus::UpToDate => "up to date",
+        })
+    }
+}
+
+/// Hash the files that the image of `lang` is built from
+pub fn language_hash(lang: &str) -> anyhow::Result<String> {
+    let dir = path::LANGUAGES.join(lang);
+    let dockerfile = dir.join("Dockerfile");
+    let mut files = Vec::new();
+    for path in image_files(
+        &fs::read_to_string(&dockerfile)
+            .with_context(|| format!("While reading `{}`", dockerfile.display()))?,
+    ) {
+        files.extend(files_in(&dir, &path)?);
+    }
+    files.sort();
+    files.dedup();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        let path = dir.join(&file);
+        let content =
+            fs::read(&path).with_context(|| format!("While reading `{}`", path.display()))?;
+        hasher.update(file.as_bytes());
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(content);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Check whether the image of `lang` exists and was built from its current
+/// files
+pub fn image_status(docker: &str, lang: &str, cfg: &LanguageConfig) -> anyhow::Result<ImageStatus> {
+    let out = Command::new(docker)
+        .args([
+            "image",
+            "inspect",
+            "--format",
+            &format!("{{{{ index .Config.Labels \"{HASH_LABEL}\" }}}}"),
+            &cfg.image_name,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("While running `{docker} image inspect`"))?;
+
+    if !out.status.success() {
+        return Ok(ImageStatus::Missing);
+    }
+    let hash = language_hash(lang)?;
+    Ok(match String::from_utf8_lossy(&out.stdout).trim() == hash {
+        true => ImageStatus::UpToDate,
+        false => ImageStatus::Outdated,
+    })
+}
+
+/// Build the image of `lang`, labelling it with the hash of the files it was
+/// built from
+pub fn build_image(docker: &str, lang: &str, cfg: &LanguageConfig) -> anyhow::Result<()> {
+    let hash = language_hash(lang)?;
+    let status = Command::new(docker)
+        .args([
+            "build",
+            "--label",
+            &format!("{HASH_LABEL}={hash}"),
+            "-t",
+            &cfg.image_name,
+            ".",
+        ])
+        .current_dir(path::LANGUAGES.join(lang))
+        .status()
+        .with_context(|| format!("While running `{docker} build`"))?;
+
+    anyhow::ensure!(
+        status.success(),
+        "`{docker} build` for `{lang}` failed with {status}"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_image_files() {
+        let dockerfile = "FROM rust:slim\n\
+                          RUN cargo new runner\n\
+                          ADD Cargo.toml /opt/runner/\n\
+                          copy --chown=runner run.sh lib.sh /opt/runner/\n\
+                          ADD https://example.com/tool.tar.gz /opt/\n\
+                          ADD run.sh /opt/runner/\n";
+        assert_eq!(
+            image_files(dockerfile),
+            ["Cargo.toml", "Dockerfile", "lib.sh", "run.sh"]
+        );
+
+        let dockerfile = "FROM python:slim\n\
+                          COPY [\"run.sh\", \"lib dir/\", \"/opt/\"]\n\
+                          ADD --chown=runner [\"main.py\", \"/opt/\"]\n";
+        assert_eq!(
+            image_files(dockerfile),
+            ["Dockerfile", "lib dir/", "main.py", "run.sh"]
+        );
+    }
+
+    #[test]
+    fn test_files_in() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("lib/nested")).unwrap();
+        for file in ["Dockerfile", "run.sh", "lib/a.sh", "lib/nested/b.sh"] {
+            fs::write(dir.path().join(file), file).unwrap();
+        }
+
+        assert_eq!(files_in(dir.path(), "run.sh").unwrap(), ["run.sh"]);
+        assert_eq!(
+            files_in(dir.path(), "lib/").unwrap(),
+            ["lib/a.sh", "lib/nested/b.sh"]
+        );
+        assert_eq!(
+            files_in(dir.path(), ".").unwrap(),
+            ["Dockerfile", "lib/a.sh", "lib/nested/b.sh", "run.sh"]
+        );
+    }
+}
@@ -1,48 +1,53 @@
 use std::{error, fmt, str::FromStr};
 
-use enum_iterator::Sequence;
-use serde::{Deserialize, Serialize};
-
-#[derive(Sequence, Debug, Serialize, Deserialize, PartialEq, Hash, Eq, Clone, Copy)]
-pub enum Language {
-    #[serde(rename = "c")]
-    C,
-    #[serde(rename = "cpp")]
-    Cpp,
-    #[serde(rename = "javascript")]
-    #[serde(alias = "js")]
-    JavaScript,
-    #[serde(rename = "python")]
-    #[serde(alias = "py")]
-    Python,
-    #[serde(rename = "rust")]
-    #[serde(alias = "rs")]
-    Rust,
-    #[serde(rename = "java")]
-    Java,
-}
+use amplitude_common::config::{languages, LanguageConfig};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A language from `languages.toml`, which is identified by its name, its
+/// extension or any of its aliases. Only languages loaded with the config by
+/// `DockerConfig::load_languages` can be found.
+#[derive(PartialEq, Hash, Eq, Clone, Copy)]
+pub struct Language(&'static str);
 
 impl Language {
-    pub fn image(&self) -> &'static str {
-        match self {
-            Self::C => "c",
-            Self::Cpp => "cpp",
-            Self::JavaScript => "javascript",
-            Self::Python => "python",
-            Self::Rust => "rust",
-            Self::Java => "java",
-        }
+    /// Every language in `languages.toml`
+    pub fn all() -> impl Iterator<Item = Language> {
+        languages().keys().map(|name| Language(name))
+    }
+
+    /// Find the language whose source files end in `.<ext>`
+    pub fn from_extension(ext: &str) -> Option<Language> {
+        Self::all().find(|lang| lang.extension() == ext)
+    }
+
+    pub fn config(&self) -> &'static LanguageConfig {
+        &languages()[self.0]
+    }
+
+    /// The name of the language, which is also the name of its directory in
+    /// `languages/`
+    pub fn name(&self) -> &'static str {
+        self.0
     }
 
     pub fn extension(&self) -> &'static str {
-        match self {
-            Self::C => "c",
-            Self::Cpp => "cpp",
-            Self::JavaScript => "js",
-            Self::Python => "py",
-            Self::Rust => "rs",
-            Self::Java => "java",
-        }
+        &self.config().extension
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        &self.config().display_name
+    }
+}
+
+impl fmt::Debug for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
     }
 }
 
@@ -61,14 +66,50 @@ impl FromStr for Language {
     type Err = LanguageIdentError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.to_ascii_lowercase().as_str() {
-            "c" => Language::C,
-            "c++" | "cpp" => Language::Cpp,
-            "javascript" | "js" => Language::JavaScript,
-            "python" | "py" => Language::Python,
-            "rust" | "rs" => Language::Rust,
-            "java" => Language::Java,
-            _ => return Err(LanguageIdentError),
-        })
+        let s = s.to_ascii_lowercase();
+        languages()
+            .iter()
+            .find(|(name, cfg)| cfg.idents(name).any(|ident| ident == s))
+            .map(|(name, _)| Language(name))
+            .ok_or(LanguageIdentError)
+    }
+}
+
+impl Serialize for Language {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| de::Error::custom(format!("unknown language `{s}`")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    #[test]
+    fn test_language() {
+        config_and_set_path().unwrap();
+        let python = Language::from_str("python").unwrap();
+        assert_eq!(Language::from_str("py").unwrap(), python);
+        assert_eq!(Language::from_str("Python").unwrap(), python);
+        assert_eq!(Language::from_extension("py"), Some(python));
+        assert_eq!(python.display_name(), "Python");
+        assert_eq!(
+            Language::from_str("c++").unwrap(),
+            Language::from_str("cpp").unwrap()
+        );
+        assert!(Language::from_str("brainfuck").is_err());
+
+        assert_eq!(serde_json::to_string(&python).unwrap(), "\"python\"");
+        assert_eq!(serde_json::from_str::<Language>("\"py\"").unwrap(), python);
     }
 }
@@ -1,41 +1,22 @@
 #![feature(iter_intersperse)]
 
+pub mod cache;
+pub mod class;
+pub mod constraint;
+pub mod diagnostics;
 pub mod exercise;
+pub mod formatter;
+pub mod health;
+pub mod images;
 pub mod lang;
+pub mod lint;
+pub mod mutation;
+pub mod performance;
 pub mod runner;
+pub mod shell;
+pub mod similarity;
+pub mod sql;
+pub mod trace;
 pub mod var_type;
-
-use amplitude_common::{
-    config::LanguageConfig,
-    path::{self, scope_dir},
-};
-use std::{
-    collections::HashMap,
-    env, fs,
-    process::{self, Command},
-};
-
-pub fn rebuild_images() {
-    // maybe i can figure out whats goin on here
-    dbg!(env::current_dir().unwrap());
-    dbg!(fs::read_dir(".").unwrap());
-    scope_dir(&path::LANGUAGES, || {
-        let langs: HashMap<String, LanguageConfig> =
-            toml::from_str(&fs::read_to_string("languages.toml").unwrap()).unwrap();
-
-        for (lang, cfg) in langs {
-            scope_dir(lang, || {
-                let run = Command::new("docker")
-                    .args(["build", "-t", &cfg.image_name, "."])
-                    .status()
-                    .unwrap();
-
-                if !run.success() {
-                    process::exit(-1);
-                }
-            })
-            .unwrap();
-        }
-    })
-    .unwrap();
-}
+pub mod variant;
+pub mod worker;
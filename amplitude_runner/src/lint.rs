@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use amplitude_common::config::Config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostics::{self, Diagnostic},
+    lang::Language,
+    runner::{run, RunOptions, RunOutput},
+};
+
+/// Lints submissions in languages that have a linter
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StyleConfig {
+    /// The style score of a submission the linter finds nothing in, if
+    /// submissions are scored at all
+    #[serde(default)]
+    pub points: Option<u32>,
+    /// The points taken off for each thing the linter finds
+    #[serde(default = "penalty_default")]
+    pub penalty: u32,
+}
+
+fn penalty_default() -> u32 {
+    1
+}
+
+/// What the linter found in a submission
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Style {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Out of `StyleConfig::points`
+    pub score: Option<u32>,
+}
+
+impl StyleConfig {
+    pub fn score(&self, findings: usize) -> Option<u32> {
+        let penalty = self
+            .penalty
+            .saturating_mul(findings.try_into().unwrap_or(u32::MAX));
+        self.points.map(|p| p.saturating_sub(penalty))
+    }
+
+    /// Run the language's linter on `code` and the student's other `files`.
+    /// Languages without a linter have no style.
+    pub fn lint(
+        &self,
+        lang: &Language,
+        cfg: &Config,
+        code: &str,
+        files: &HashMap<String, String>,
+    ) -> anyhow::Result<Option<Style>> {
+        let lang = cfg.docker.language_config.get(lang.name()).unwrap();
+        let Some(lint) = &lang.lint else {
+            return Ok(None);
+        };
+
+        let RunOutput {
+            stdout,
+            stderr,
+            truncated,
+            ..
+        } = run(
+            lang,
+            &cfg.docker,
+            code,
+            files
+                .iter()
+                .map(|(path, content)| (path.clone(), content.as_bytes()))
+                .collect(),
+            &RunOptions {
+                command: Some(&lint.command),
+                ..Default::default()
+            },
+        )
+        .context("While running linter")?;
+        anyhow::ensure!(
+            !truncated,
+            "Linter output exceeded the limit of {} bytes",
+            cfg.docker.output_limit
+        );
+
+        let files = files.keys().map(|f| f.as_str()).collect::<Vec<_>>();
+        let (_, diagnostics) = diagnostics::parse(
+            lint.format,
+            &format!("{stdout}{stderr}"),
+            &lang.source_path,
+            &files,
+        );
+        Ok(Some(Style {
+            score: self.score(diagnostics.len()),
+            diagnostics,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let style = StyleConfig {
+            points: Some(5),
+            penalty: 2,
+        };
+        assert_eq!(style.score(0), Some(5));
+        assert_eq!(style.score(2), Some(1));
+        assert_eq!(style.score(3), Some(0));
+        assert_eq!(StyleConfig::default().score(3), None);
+    }
+
+    #[test]
+    fn test_lint() {
+        let cfg = config_and_set_path().unwrap();
+        let lang: Language = "python".parse().unwrap();
+        let style = StyleConfig {
+            points: Some(10),
+            penalty: 1,
+        };
+
+        let files = HashMap::from_iter([(
+            "util.py".to_string(),
+            "def f():\n    return y\n".to_string(),
+        )]);
+        let found = style
+            .lint(&lang, &cfg, "import os\nimport util\n", &files)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.score, Some(8));
+        let mut lines = found
+            .diagnostics
+            .iter()
+            .map(|d| (d.file.as_deref(), d.line))
+            .collect::<Vec<_>>();
+        lines.sort();
+        assert_eq!(lines, vec![(None, 1), (Some("util.py"), 2)]);
+    }
+}
@@ -1,15 +1,201 @@
-use std::env;
+use std::{collections::HashMap, env, fs, path::PathBuf, process};
 
-use amplitude_runner::rebuild_images;
+use amplitude_common::{
+    config::{DockerConfig, LanguageConfig},
+    path,
+};
+use amplitude_runner::{
+    images::{build_image, image_status, ImageStatus},
+    runner::{run, RunOptions, RunOutput},
+};
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+
+/// Manage the docker images that amplitude runs code in
+#[derive(Parser, Debug)]
+struct Cli {
+    /// The path of the config file
+    #[arg(long, default_value = "config.toml")]
+    config: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build the images of languages whose `Dockerfile` or other files changed
+    Build {
+        /// Only build the image of this language
+        lang: Option<String>,
+        /// Rebuild even if nothing changed
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+    /// Check that the image of every language is built and up to date
+    Check,
+    /// List the configured languages
+    List,
+    /// Run a file in the image of a language
+    Run {
+        lang: String,
+        file: PathBuf,
+        /// Arguments to pass to the program
+        #[arg(long, default_value = "")]
+        args: String,
+        /// A file to pass to the program's stdin
+        #[arg(long)]
+        stdin: Option<PathBuf>,
+        /// Run the language's linter on the file instead
+        #[arg(long)]
+        lint: bool,
+    },
+}
+
+/// The parts of the config file we care about
+#[derive(Deserialize, Debug)]
+struct RunnerConfig {
+    docker: DockerConfig,
+}
+
+fn language<'a>(cfg: &'a DockerConfig, lang: &str) -> anyhow::Result<&'a LanguageConfig> {
+    cfg.language_config.get(lang).with_context(|| {
+        let mut langs = cfg.language_config.keys().cloned().collect::<Vec<_>>();
+        langs.sort();
+        format!(
+            "Language `{lang}` not found, expected one of: {}",
+            langs.join(", ")
+        )
+    })
+}
+
+fn sorted_languages(cfg: &DockerConfig) -> Vec<(&String, &LanguageConfig)> {
+    let mut langs = cfg.language_config.iter().collect::<Vec<_>>();
+    langs.sort_by_key(|(name, _)| *name);
+    langs
+}
 
 fn main() {
-    // let binding = env::current_dir().unwrap().to_path_buf();
-    // let dir = binding
-    //     .components()
-    //     .take_while(|c| c.as_os_str() != "amplitude")
-    //     .collect::<PathBuf>()
-    //     .join("amplitude");
-    // env::set_current_dir(&dir).unwrap();
-    env::set_current_dir("..").unwrap();
-    rebuild_images()
+    let cli = Cli::parse();
+    if let Err(e) = run_cli(cli) {
+        eprintln!("error: {e:#}");
+        process::exit(1);
+    }
+}
+
+fn run_cli(cli: Cli) -> anyhow::Result<()> {
+    let cwd = env::current_dir().context("While getting the current directory")?;
+    // allow running from inside `amplitude_runner` with `cargo r`
+    if !path::LANGUAGES.as_path().exists() {
+        env::set_current_dir("..").context("While moving to the parent directory")?;
+    }
+    anyhow::ensure!(
+        path::LANGUAGES.as_path().exists(),
+        "Could not find the `{}` directory",
+        path::LANGUAGES
+    );
+
+    let mut config: RunnerConfig = toml::from_str(
+        &fs::read_to_string(&cli.config)
+            .with_context(|| format!("While reading `{}`", cli.config.display()))?,
+    )
+    .context("While parsing config file")?;
+    config.docker.load_languages()?;
+    let docker = &config.docker;
+
+    match cli.command {
+        Command::Build { lang, force } => {
+            let langs = match &lang {
+                Some(lang) => vec![(lang, language(docker, lang)?)],
+                None => sorted_languages(docker),
+            };
+            for (lang, cfg) in langs {
+                let status = image_status(&docker.command, lang, cfg)?;
+                if status == ImageStatus::UpToDate && !force {
+                    println!("{lang}: {status}, skipping");
+                    continue;
+                }
+                println!("{lang}: {status}, building `{}`", cfg.image_name);
+                build_image(&docker.command, lang, cfg)
+                    .with_context(|| format!("While building image for `{lang}`"))?;
+            }
+        }
+        Command::Check => {
+            let mut outdated = 0;
+            for (lang, cfg) in sorted_languages(docker) {
+                let status = image_status(&docker.command, lang, cfg)?;
+                println!("{lang:12} {:20} {status}", cfg.image_name);
+                if status != ImageStatus::UpToDate {
+                    outdated += 1;
+                }
+            }
+            anyhow::ensure!(
+                outdated == 0,
+                "{outdated} image(s) need to be built, try running `build`"
+            );
+        }
+        Command::List => {
+            for (lang, cfg) in sorted_languages(docker) {
+                println!("{lang:12} {:20} .{}", cfg.image_name, cfg.extension);
+            }
+        }
+        Command::Run {
+            lang,
+            file,
+            args,
+            stdin,
+            lint,
+        } => {
+            let cfg = language(docker, &lang)?;
+            let command = match lint {
+                true => Some(
+                    cfg.lint
+                        .as_ref()
+                        .with_context(|| format!("`{lang}` has no linter"))?
+                        .command
+                        .as_str(),
+                ),
+                false => None,
+            };
+            let file = cwd.join(file);
+            let src = fs::read_to_string(&file)
+                .with_context(|| format!("While reading `{}`", file.display()))?;
+            let stdin = match stdin {
+                Some(stdin) => {
+                    let stdin = cwd.join(stdin);
+                    Some(
+                        fs::read_to_string(&stdin)
+                            .with_context(|| format!("While reading `{}`", stdin.display()))?,
+                    )
+                }
+                None => None,
+            };
+            let opts = RunOptions {
+                args: &args,
+                stdin: stdin.as_deref(),
+                command,
+            };
+            let RunOutput {
+                stdout,
+                stderr,
+                runtime,
+                exit_code,
+                truncated,
+                compile_error,
+            } = run(cfg, docker, &src, HashMap::new(), &opts).context("While running file")?;
+
+            print!("{stdout}");
+            eprint!("{stderr}");
+            if truncated {
+                eprintln!("output was truncated");
+            }
+            if compile_error {
+                eprintln!("failed to compile");
+            }
+            eprintln!("exited with code {exit_code} after {runtime:?}");
+            process::exit(exit_code);
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashMap};
+
+use amplitude_common::config::Config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lang::Language,
+    runner::{run, RunOptions, RunOutput},
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MutationConfig {
+    /// What the student's tests import the implementation as
+    #[serde(default = "module_default")]
+    pub module: String,
+    /// The buggy implementations, by name
+    #[serde(skip)]
+    pub mutants: BTreeMap<String, String>,
+}
+
+fn module_default() -> String {
+    "implementation".to_string()
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self {
+            module: module_default(),
+            mutants: BTreeMap::new(),
+        }
+    }
+}
+
+/// How well a student's tests tell the correct implementation apart from the
+/// buggy ones
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MutationResults {
+    /// Whether the tests pass against the correct implementation
+    pub correct_passes: bool,
+    /// What the tests printed against the correct implementation. Never sent
+    /// to students, since their tests could print the implementation.
+    #[serde(skip)]
+    pub stdout: String,
+    #[serde(skip)]
+    pub stderr: String,
+    /// How many mutants made the tests fail
+    pub killed: usize,
+    pub total: usize,
+    /// The mutants the tests didn't catch
+    pub survived: Vec<String>,
+    pub passed: bool,
+}
+
+impl MutationConfig {
+    /// Run `tests` against `implementation`, returning the output and whether
+    /// they passed
+    fn run_against(
+        &self,
+        lang: &Language,
+        cfg: &Config,
+        tests: &str,
+        implementation: &str,
+        files: &HashMap<String, &[u8]>,
+    ) -> anyhow::Result<(RunOutput, bool)> {
+        let mut files = files.clone();
+        files.insert(
+            format!("{}.{}", self.module, lang.extension()),
+            implementation.as_bytes(),
+        );
+        let output = run(
+            lang.config(),
+            &cfg.docker,
+            tests,
+            files,
+            &RunOptions::default(),
+        )
+        .context("While running tests")?;
+        let passed = output.exit_code == 0 && !output.truncated;
+        Ok((output, passed))
+    }
+
+    /// Run the student's `tests` against the correct `implementation` and
+    /// every mutant. Tests fail by exiting with a nonzero code.
+    pub fn run(
+        &self,
+        lang: &Language,
+        cfg: &Config,
+        tests: &str,
+        implementation: &str,
+        files: &HashMap<String, &[u8]>,
+    ) -> anyhow::Result<MutationResults> {
+        let (output, correct_passes) = self
+            .run_against(lang, cfg, tests, implementation, files)
+            .context("While running against the correct implementation")?;
+
+        let mut survived = Vec::new();
+        for (name, mutant) in &self.mutants {
+            let (_, passed) = self
+                .run_against(lang, cfg, tests, mutant, files)
+                .with_context(|| format!("While running against mutant `{name}`"))?;
+            if passed {
+                survived.push(name.clone());
+            }
+        }
+
+        let total = self.mutants.len();
+        let killed = total - survived.len();
+        Ok(MutationResults {
+            correct_passes,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            killed,
+            total,
+            passed: correct_passes && survived.is_empty(),
+            survived,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    #[test]
+    fn test_mutation() {
+        let cfg = config_and_set_path().unwrap();
+        let lang: Language = "python".parse().unwrap();
+        let mutation = MutationConfig {
+            mutants: BTreeMap::from_iter([
+                (
+                    "off_by_one".to_string(),
+                    "def add(a, b):\n    return a + b + 1\n".to_string(),
+                ),
+                (
+                    "negative".to_string(),
+                    "def add(a, b):\n    return abs(a) + abs(b)\n".to_string(),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let correct = "def add(a, b):\n    return a + b\n";
+        let run = |tests| {
+            mutation
+                .run(&lang, &cfg, tests, correct, &HashMap::new())
+                .unwrap()
+        };
+
+        let results = run("from implementation import add\nassert add(1, 2) == 3\n");
+        assert!(results.correct_passes);
+        assert_eq!(results.killed, 1);
+        assert_eq!(results.survived, vec!["negative"]);
+        assert!(!results.passed);
+
+        let results = run(
+            "from implementation import add\nassert add(1, 2) == 3\nassert add(-1, -1) == -2\n",
+        );
+        assert!(results.passed);
+
+        let results = run("from implementation import add\nassert add(1, 2) == 4\n");
+        assert!(!results.correct_passes);
+        assert!(!results.passed);
+        let json = serde_json::to_value(&results).unwrap();
+        assert!(json.get("stderr").is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    constraint::{generate_cases, Constraints},
+    exercise::{FunctionConfig, TestCase, TestOutput},
+};
+
+/// The least time a submission is given for all the large inputs, so tiny
+/// reference runtimes don't make the budget all noise
+pub const MIN_BUDGET_SECS: f64 = 0.05;
+
+/// Times a function on large hidden inputs, against how long the reference
+/// solution takes on them
+///
+/// ```toml
+/// [functions.sort.performance]
+/// constraints = [{ len = [50000, 50000], items = { min = 0, max = 1000000 } }]
+/// factor = 3.0
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PerformanceConfig {
+    /// Constraints on each input, used to generate the large inputs
+    pub constraints: Vec<Constraints>,
+    /// How many large inputs there are
+    #[serde(default = "cases_default")]
+    pub cases: u32,
+    /// How many times the reference solution's runtime a submission can take
+    pub factor: f64,
+    /// The large inputs, with digests of the reference solution's outputs,
+    /// since runner templates only print those
+    #[serde(skip)]
+    pub tests: Vec<TestCase>,
+    /// How many seconds the reference solution took on all of `tests`, once it
+    /// has been run
+    #[serde(skip)]
+    pub reference: Option<f64>,
+}
+
+const fn cases_default() -> u32 {
+    3
+}
+
+/// What a runner template prints for one of the large inputs, the output
+/// being a digest of what the function returned
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timed {
+    pub output: TestOutput,
+    /// In seconds
+    pub time: f64,
+}
+
+/// How a submission did on the large inputs
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PerformanceResult {
+    /// Whether every large input got the right answer within the time budget
+    pub passed: bool,
+    /// How many times the reference solution's runtime the submission took
+    pub ratio: f64,
+}
+
+impl PerformanceConfig {
+    /// Generate the large inputs for `func` from `constraints`. The outputs are
+    /// filled in by the reference solution.
+    pub fn generate_cases(&self, func: &FunctionConfig) -> anyhow::Result<Vec<TestCase>> {
+        generate_cases(&FunctionConfig {
+            constraints: self.constraints.clone(),
+            // so the large inputs don't start out the same as the test cases
+            seed: func.seed.wrapping_add(1),
+            hidden_cases: self.cases,
+            visible_cases: 0,
+            ..func.clone()
+        })
+    }
+
+    /// How many seconds a submission has for all the large inputs, once the
+    /// reference solution has been timed
+    pub fn budget(&self) -> Option<f64> {
+        self.reference
+            .map(|r| (r * self.factor).max(MIN_BUDGET_SECS))
+    }
+
+    /// What the runner template needs to time the function
+    pub fn runner_data(&self) -> Value {
+        serde_json::json!({
+            "inputs": self.tests.iter().map(|t| &t.inputs).collect::<Vec<_>>(),
+            "budget": self.budget(),
+        })
+    }
+
+    /// Compare the submission's outputs on the large inputs with the reference
+    /// solution's. Runner templates stop timing once the budget is used up, so
+    /// there can be fewer outputs than inputs.
+    pub fn check(&self, timed: &[Timed]) -> PerformanceResult {
+        let time = timed.iter().map(|t| t.time).sum::<f64>();
+        let correct = timed.len() == self.tests.len()
+            && self.tests.iter().zip(timed).all(|(test, t)| {
+                matches!(&t.output, TestOutput::Answer { value, .. } if value == &test.output)
+            });
+        let budget = self.budget().unwrap_or(f64::INFINITY);
+        PerformanceResult {
+            passed: correct && time <= budget,
+            ratio: time / self.reference.unwrap_or(0.0).max(f64::EPSILON),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn timed(value: Value, time: f64) -> Timed {
+        Timed {
+            output: TestOutput::Answer {
+                value,
+                stdout: String::new(),
+            },
+            time,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut perf = PerformanceConfig {
+            constraints: vec![],
+            cases: 2,
+            factor: 2.0,
+            tests: vec![
+                TestCase {
+                    inputs: vec![json!(1)],
+                    output: json!(2),
+                    hidden: true,
+                },
+                TestCase {
+                    inputs: vec![json!(2)],
+                    output: json!(4),
+                    hidden: true,
+                },
+            ],
+            reference: Some(1.0),
+        };
+        assert_eq!(perf.budget(), Some(2.0));
+
+        let result = perf.check(&[timed(json!(2), 0.5), timed(json!(4), 1.0)]);
+        assert!(result.passed);
+        assert_eq!(result.ratio, 1.5);
+
+        let result = perf.check(&[timed(json!(2), 1.5), timed(json!(4), 1.0)]);
+        assert!(!result.passed);
+        assert_eq!(result.ratio, 2.5);
+
+        // wrong answers and inputs left out fail however fast they are
+        assert!(
+            !perf
+                .check(&[timed(json!(2), 0.1), timed(json!(5), 0.1)])
+                .passed
+        );
+        assert!(!perf.check(&[timed(json!(2), 0.1)]).passed);
+
+        perf.reference = Some(0.0);
+        assert_eq!(perf.budget(), Some(MIN_BUDGET_SECS));
+    }
+
+    #[test]
+    fn test_generate_cases() {
+        let func: FunctionConfig = toml::from_str(
+            "inputs = ['int[]']\noutput = 'int'\n\
+             [performance]\nconstraints = [{ len = [100, 100] }]\nfactor = 2.0\n",
+        )
+        .unwrap();
+        let perf = func.performance.as_ref().unwrap();
+        let cases = perf.generate_cases(&func).unwrap();
+        assert_eq!(cases.len(), 3);
+        for case in cases {
+            assert!(case.hidden);
+            assert_eq!(case.inputs[0].as_array().unwrap().len(), 100);
+        }
+    }
+}
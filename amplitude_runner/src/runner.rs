@@ -1,8 +1,14 @@
 use std::{
     collections::HashMap,
     fs,
-    io::BufRead,
+    io::{self, Read, Write},
+    path::{Component, Path},
     process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    thread,
     time::{Duration, Instant},
 };
 
@@ -28,55 +34,128 @@ pub fn url_encode(url: &str) -> String {
     out
 }
 
+/// The line a language's `run.sh` prints to stderr after the compiler's output
+/// when the code doesn't compile
+pub const COMPILE_ERROR_SENTINEL: &str = "::amplitude-compile-error::";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RunOutput {
     pub stdout: String,
     pub stderr: String,
     pub runtime: Duration,
     pub exit_code: i32,
+    /// Whether the program was killed for printing more than
+    /// `DockerConfig::output_limit` bytes
+    pub truncated: bool,
+    /// Whether the code didn't compile, in which case `stderr` is the
+    /// compiler's output
+    #[serde(default)]
+    pub compile_error: bool,
+}
+
+/// Read `pipe` to the end, keeping at most `limit` bytes. `overflow` is called
+/// once the limit is passed, and everything after that is thrown away.
+fn capture(mut pipe: impl Read, limit: usize, overflow: impl Fn()) -> io::Result<(Vec<u8>, bool)> {
+    let mut out = Vec::new();
+    let mut buf = [0; 8192];
+    let mut truncated = false;
+    loop {
+        let n = pipe.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if truncated {
+            continue;
+        }
+        out.extend_from_slice(&buf[..n]);
+        if out.len() > limit {
+            out.truncate(limit);
+            truncated = true;
+            overflow();
+        }
+    }
+    Ok((out, truncated))
+}
+
+/// Check that `path` is relative, stays inside the directory it's joined onto
+/// and can be passed to `docker run -v`
+pub fn check_path(path: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(!path.is_empty(), "File path is empty");
+    anyhow::ensure!(
+        !path.contains([':', ',', '\\', '\0']),
+        "File path `{path}` contains an invalid character"
+    );
+    anyhow::ensure!(
+        Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_))),
+        "File path `{path}` must be relative, and can't contain `.` or `..`"
+    );
+    Ok(())
 }
 
+/// Everything about a run other than the code and files
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunOptions<'a> {
+    /// Arguments to pass to the program
+    pub args: &'a str,
+    /// What to write to the program's stdin, which is closed otherwise
+    pub stdin: Option<&'a str>,
+    /// A command run with bash in place of the image's `run.sh`, with the
+    /// same timeout
+    pub command: Option<&'a str>,
+}
+
+/// Somewhere other than docker on this machine that code can be run, like the
+/// server's remote workers
+pub trait Sandbox: Send + Sync {
+    fn run(
+        &self,
+        lang: &LanguageConfig,
+        src: &str,
+        other_files: HashMap<String, &[u8]>,
+        opts: &RunOptions,
+    ) -> anyhow::Result<RunOutput>;
+}
+
+static SANDBOX: OnceLock<Box<dyn Sandbox>> = OnceLock::new();
+
+/// Run all code with `sandbox` from now on. Can only be set once.
+pub fn set_sandbox(sandbox: Box<dyn Sandbox>) -> anyhow::Result<()> {
+    SANDBOX
+        .set(sandbox)
+        .map_err(|_| anyhow::anyhow!("Sandbox was already set"))
+}
+
+/// Run code with the sandbox if one was set, or with docker on this machine
 pub fn run(
     lang: &LanguageConfig,
     cfg: &DockerConfig,
     src: &str,
     other_files: HashMap<String, &[u8]>,
-    args: &str,
+    opts: &RunOptions,
 ) -> anyhow::Result<RunOutput> {
-    // test that the docker image exists
-    #[cfg(debug_assertions)]
-    {
-        let out = Command::new(&cfg.command)
-            .arg("images")
-            .arg(&lang.image_name)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .unwrap()
-            .wait_with_output()
-            .context("While running `docker images`")?;
-        if out
-            .stdout
-            .lines()
-            .skip(1)
-            .filter_map(|x| x.ok())
-            .all(|x| !x.starts_with(&lang.image_name))
-        {
-            dbg!(
-                "Image {} not found! try running `cd amplitude_runner` and `cargo r` to rebuild \
-                 docker images",
-                &lang.image_name
-            );
-            std::process::exit(-1);
-        }
+    match SANDBOX.get() {
+        Some(sandbox) => sandbox.run(lang, src, other_files, opts),
+        None => run_local(lang, cfg, src, other_files, opts),
     }
+}
 
+/// Run code with docker on this machine
+pub fn run_local(
+    lang: &LanguageConfig,
+    cfg: &DockerConfig,
+    src: &str,
+    other_files: HashMap<String, &[u8]>,
+    opts: &RunOptions,
+) -> anyhow::Result<RunOutput> {
     let tempdir = tempfile::tempdir_in(&cfg.tmp_folder).context("While creating temp dir")?;
     let code_path = tempdir.path().join(&lang.source_path);
     fs::create_dir_all(code_path.parent().unwrap()).context("While creating temp dir")?;
     fs::write(&code_path, src).context("While writing source code")?;
 
     for (path, content) in &other_files {
+        check_path(path)?;
         let path = tempdir.path().join(path);
         fs::create_dir_all(path.parent().unwrap()).context("While creating temp dir")?;
         fs::write(&path, content).context("While writing file")?;
@@ -89,21 +168,42 @@ pub fn run(
         .chain(other_files.keys())
         .map(|path| {
             format!(
-                "{}:/runner/{}",
+                "{}:/runner/{}:ro",
                 tempdir.path().join(path).to_string_lossy(),
                 &path
             )
         })
         .collect::<Vec<_>>();
 
+    // named so it can be killed if it prints too much
+    let name = format!(
+        "amplitude-{}",
+        tempdir.path().file_name().unwrap().to_string_lossy()
+    )
+    .replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "");
+    let tmpfs = format!("rw,exec,nosuid,size={},mode=1777", cfg.tmpfs_size);
+    // the command is passed as an argument so it doesn't have to be quoted
+    let command = opts
+        .command
+        .map(|c| ["-c", r#"timeout --signal=KILL ${TIMEOUT} bash -c "$0""#, c]);
+
     // tried to use bollard instead of using a command but that was even worse
-    let run = Command::new(&cfg.command)
+    let mut child = Command::new(&cfg.command)
         .args(
             [
                 "run",
                 "--rm",
+                "--name",
+                &name,
                 "--cap-drop=ALL",
                 "--security-opt=no-new-privileges",
+                "--security-opt",
+                &format!("seccomp={}", cfg.seccomp.to_string_lossy()),
+                "--read-only",
+                "--tmpfs",
+                &format!("/runner:{tmpfs}"),
+                "--tmpfs",
+                &format!("/tmp:{tmpfs}"),
                 "--net",
                 "none",
                 "--memory",
@@ -115,33 +215,89 @@ pub fn run(
                 "-e",
                 &format!("TIMEOUT={}", &cfg.timeout),
                 "-e",
-                &format!("ARGS={}", url_encode(args)),
+                &format!("ARGS={}", url_encode(opts.args)),
                 "-v",
             ]
             .into_iter()
             .chain(v.iter().map(|x| x.as_str()).intersperse("-v"))
-            .chain([lang.image_name.as_str()]),
+            .chain(opts.stdin.map(|_| "-i"))
+            .chain(
+                opts.command
+                    .is_some()
+                    .then_some(["--entrypoint", "bash"])
+                    .into_iter()
+                    .flatten(),
+            )
+            .chain([lang.image_name.as_str()])
+            .chain(command.into_iter().flatten()),
         )
+        .stdin(match opts.stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .unwrap()
-        .wait_with_output()
-        .unwrap();
+        .with_context(|| format!("While running `{} run`", cfg.command))?;
+
+    let killed = AtomicBool::new(false);
+    let kill = || {
+        if !killed.swap(true, Ordering::SeqCst) {
+            // if this fails the container will still be stopped by the timeout
+            let _ = Command::new(&cfg.command)
+                .args(["kill", &name])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    };
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let (stdout, stderr) = thread::scope(|s| {
+        if let (Some(mut pipe), Some(stdin)) = (stdin, opts.stdin) {
+            // the program doesn't have to read all of it, so errors are ignored
+            s.spawn(move || pipe.write_all(stdin.as_bytes()));
+        }
+        let stdout = s.spawn(|| capture(stdout, cfg.output_limit, kill));
+        let stderr = capture(stderr, cfg.output_limit, kill);
+        (stdout.join().unwrap(), stderr)
+    });
+    let ((stdout, stdout_truncated), (stderr, stderr_truncated)) = (
+        stdout.context("While reading stdout")?,
+        stderr.context("While reading stderr")?,
+    );
+    let status = child
+        .wait()
+        .context("While waiting for container to exit")?;
+
+    let mut stderr = String::from_utf8_lossy(&stderr).to_string();
+    let compile_error = match stderr.strip_suffix(&format!("{COMPILE_ERROR_SENTINEL}\n")) {
+        Some(output) => {
+            stderr.truncate(output.len());
+            true
+        }
+        None => false,
+    };
 
     Ok(RunOutput {
-        stdout: String::from_utf8_lossy(&run.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&run.stderr).to_string(),
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr,
         runtime: time.elapsed(),
-        exit_code: run.status.code().unwrap(),
+        exit_code: status.code().unwrap_or(-1),
+        truncated: stdout_truncated || stderr_truncated,
+        compile_error,
     })
 }
 
 #[cfg(test)]
 mod test {
-    use std::{env, fs};
+    use std::{cell::Cell, env, fs};
 
-    use amplitude_common::config::{Args, Config};
+    use amplitude_common::{
+        config::{Args, Config},
+        config_and_set_path,
+    };
 
     use super::*;
 
@@ -151,6 +307,7 @@ mod test {
         let args = Args::parse();
         let mut config: Config = toml::from_str::<Config>(&fs::read_to_string(&args.config)?)?;
         config.args = args;
+        config.docker.load_languages()?;
 
         let output = run(
             config
@@ -167,7 +324,7 @@ print(file.read())
 file.close()
 ",
             HashMap::from_iter([("file.txt".to_string(), "File contents".as_bytes())]),
-            "",
+            &RunOptions::default(),
         )
         .unwrap();
         dbg!(&output);
@@ -175,4 +332,128 @@ file.close()
 
         Ok(())
     }
+
+    fn run_python(src: &str) -> RunOutput {
+        run_python_with(src, &RunOptions::default())
+    }
+
+    fn run_python_with(src: &str, opts: &RunOptions) -> RunOutput {
+        let config = config_and_set_path().unwrap();
+        let lang = config.docker.language_config.get("python").unwrap();
+        run(lang, &config.docker, src, HashMap::new(), opts).unwrap()
+    }
+
+    #[test]
+    fn test_stdin() {
+        let opts = RunOptions {
+            stdin: Some("1\n2\n"),
+            ..Default::default()
+        };
+        let output = run_python_with("print(int(input()) + int(input()))", &opts);
+        assert_eq!(output.stdout, "3\n");
+
+        let output = run_python("print(input())");
+        assert!(output.stderr.contains("EOFError"));
+    }
+
+    #[test]
+    fn test_command() {
+        let opts = RunOptions {
+            command: Some("cat main.py; exit 3"),
+            ..Default::default()
+        };
+        let output = run_python_with("print('not run')", &opts);
+        assert_eq!(output.stdout, "print('not run')");
+        assert_eq!(output.exit_code, 3);
+    }
+
+    #[test]
+    fn test_check_path() {
+        assert!(check_path("main.py").is_ok());
+        assert!(check_path("src/lib.rs").is_ok());
+        for path in [
+            "",
+            "/etc/passwd",
+            "../main.py",
+            "src/../../x",
+            "./a",
+            "a:b",
+            "a,ro",
+        ] {
+            assert!(check_path(path).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_capture() {
+        let calls = Cell::new(0);
+        let (out, truncated) =
+            capture(&[b'a'; 100][..], 10, || calls.set(calls.get() + 1)).unwrap();
+        assert_eq!(out, [b'a'; 10]);
+        assert!(truncated);
+        assert_eq!(calls.get(), 1);
+
+        let (out, truncated) = capture(&b"hello"[..], 10, || panic!()).unwrap();
+        assert_eq!(out, b"hello");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let output = run_python("while True:\n    print('spam' * 100)");
+        assert!(output.truncated);
+        assert_ne!(output.exit_code, 0);
+    }
+
+    #[test]
+    fn test_read_only() {
+        let output = run_python(
+            "
+open('/runner/scratch.txt', 'w').write('ok')
+open('/tmp/scratch.txt', 'w').write('ok')
+try:
+    open('/home/evil.txt', 'w')
+except OSError:
+    print('read only')
+",
+        );
+        assert_eq!(output.stdout, "read only\n");
+    }
+
+    #[test]
+    fn test_tmpfs_size() {
+        let output = run_python(
+            "
+with open('/tmp/big', 'wb') as f:
+    for _ in range(128):
+        f.write(b'0' * 1024 * 1024)
+",
+        );
+        assert!(output.stderr.contains("No space left on device"));
+    }
+
+    #[test]
+    fn test_seccomp() {
+        let output = run_python(
+            "
+import os
+try:
+    os.unshare(os.CLONE_NEWUSER)
+except PermissionError:
+    print('blocked')
+",
+        );
+        assert_eq!(output.stdout, "blocked\n");
+
+        // not in docker's default allowlist, which the profile starts from
+        let output = run_python(
+            "
+import ctypes
+libc = ctypes.CDLL(None, use_errno=True)
+ADDR_NO_RANDOMIZE = 0x0040000
+print(libc.personality(ADDR_NO_RANDOMIZE), ctypes.get_errno())
+",
+        );
+        assert_eq!(output.stdout, "-1 1\n");
+    }
 }
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use amplitude_common::config::Config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    exercise::{TestResult, TestResults},
+    lang::Language,
+    runner::{run, RunOptions, RunOutput},
+};
+
+/// The language shell exercises are run in
+pub const SHELL_LANGUAGE: &str = "bash";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShellConfig {
+    pub tests: Vec<ShellTest>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShellTest {
+    /// Arguments the script is called with
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub stdin: Option<String>,
+    /// The directory tree the script starts in, by path. Paths ending in `/`
+    /// are empty directories.
+    #[serde(default)]
+    pub files: HashMap<String, String>,
+    #[serde(default)]
+    pub exit_code: i32,
+    /// What the script should print, ignoring trailing whitespace. Not
+    /// checked if missing.
+    #[serde(default)]
+    pub stdout: Option<String>,
+    /// What the tree should look like afterwards, by path. Paths not listed
+    /// here are not checked.
+    #[serde(default)]
+    pub expect: HashMap<String, FileExpectation>,
+    #[serde(default)]
+    pub hidden: bool,
+    /// How many seconds the script has before it's killed and the test fails
+    #[serde(default = "timeout_default")]
+    pub timeout: f64,
+}
+
+const fn timeout_default() -> f64 {
+    5.0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FileExpectation {
+    /// Whether the file (or directory) should exist at all
+    Exists(bool),
+    /// The exact contents of the file
+    Content(String),
+}
+
+/// What `languages/bash/run.py` is given for a test, leaving out what it's
+/// checked against so hidden tests stay hidden from the script
+#[derive(Debug, Serialize)]
+struct ShellSetup<'a> {
+    args: &'a [String],
+    stdin: Option<&'a str>,
+    files: &'a HashMap<String, String>,
+    timeout: f64,
+}
+
+/// What the script did in a test, as printed by `languages/bash/run.py`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ShellOutput {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    /// Everything left in the directory, with `None` for directories
+    pub files: HashMap<String, Option<String>>,
+    /// Whether the script was killed for running past the test's `timeout`
+    #[serde(default)]
+    pub timed_out: bool,
+}
+
+pub fn shell_language() -> anyhow::Result<Language> {
+    SHELL_LANGUAGE
+        .parse()
+        .with_context(|| format!("Language `{SHELL_LANGUAGE}` not found in `languages.toml`"))
+}
+
+impl ShellTest {
+    /// Why `output` fails this test, if it does
+    pub fn check(&self, output: &ShellOutput) -> Option<String> {
+        if output.timed_out {
+            return Some(format!("Timed out after {} seconds", self.timeout));
+        }
+        if output.exit_code != self.exit_code {
+            return Some(format!(
+                "Expected exit code {}, found {}",
+                self.exit_code, output.exit_code
+            ));
+        }
+        if let Some(stdout) = &self.stdout {
+            if stdout.trim_end() != output.stdout.trim_end() {
+                return Some("Printed something different".to_string());
+            }
+        }
+
+        let mut expect = self.expect.iter().collect::<Vec<_>>();
+        expect.sort_by_key(|(path, _)| *path);
+        for (path, expectation) in expect {
+            let found = output
+                .files
+                .get(path)
+                .or_else(|| output.files.get(&format!("{path}/")));
+            match (expectation, found) {
+                (FileExpectation::Exists(true), None) => {
+                    return Some(format!("Expected `{path}` to exist"))
+                }
+                (FileExpectation::Exists(false), Some(_)) => {
+                    return Some(format!("Expected `{path}` not to exist"))
+                }
+                (FileExpectation::Content(_), None) => {
+                    return Some(format!("Expected `{path}` to exist"))
+                }
+                (FileExpectation::Content(expected), Some(found))
+                    if found.as_ref() != Some(expected) =>
+                {
+                    return Some(format!("`{path}` has the wrong contents"))
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl ShellConfig {
+    /// Run `script` once for every test
+    pub fn run(
+        &self,
+        cfg: &Config,
+        script: &str,
+        fixtures: &HashMap<String, Vec<u8>>,
+    ) -> anyhow::Result<Vec<ShellOutput>> {
+        let lang = shell_language()?;
+        let tests = serde_json::to_vec(
+            &self
+                .tests
+                .iter()
+                .map(|test| ShellSetup {
+                    args: &test.args,
+                    stdin: test.stdin.as_deref(),
+                    files: &test.files,
+                    timeout: test.timeout,
+                })
+                .collect::<Vec<_>>(),
+        )?;
+        let RunOutput {
+            stdout,
+            stderr,
+            truncated,
+            ..
+        } = run(
+            lang.config(),
+            &cfg.docker,
+            script,
+            fixtures
+                .iter()
+                .map(|(path, content)| (path.clone(), &content[..]))
+                .chain([("tests.json".to_string(), &tests[..])])
+                .collect(),
+            &RunOptions {
+                args: "--tests tests.json",
+                ..Default::default()
+            },
+        )
+        .context("While running script")?;
+        anyhow::ensure!(
+            !truncated,
+            "Output exceeded the limit of {} bytes",
+            cfg.docker.output_limit
+        );
+
+        let outputs: Vec<ShellOutput> = serde_json::from_str(&stdout).with_context(|| {
+            format!("While parsing script output\nstdout:\n{stdout}\n\nstderr:\n{stderr}")
+        })?;
+        anyhow::ensure!(
+            outputs.len() == self.tests.len(),
+            "Expected {} test outputs, found {}",
+            self.tests.len(),
+            outputs.len()
+        );
+        Ok(outputs)
+    }
+
+    /// Run the student's `script` against every test. Hidden tests only count
+    /// towards `hidden`.
+    pub fn run_tests(
+        &self,
+        cfg: &Config,
+        script: &str,
+        fixtures: &HashMap<String, Vec<u8>>,
+    ) -> anyhow::Result<HashMap<String, TestResults>> {
+        let outputs = self.run(cfg, script, fixtures)?;
+
+        let mut visible_passed = true;
+        let mut hidden = true;
+        let mut results = Vec::new();
+        for (test, output) in self.tests.iter().zip(outputs) {
+            let failure = test.check(&output);
+            if test.hidden {
+                hidden &= failure.is_none();
+                continue;
+            }
+            visible_passed &= failure.is_none();
+            results.push(match failure {
+                None => TestResult::Correct {
+                    stdout: output.stdout,
+                },
+                Some(reason) => TestResult::Incorrect {
+                    stdout: output.stdout.clone(),
+                    output: json!({ "reason": reason, "output": output }),
+                },
+            });
+        }
+
+        Ok(HashMap::from_iter([(
+            "script".to_string(),
+            TestResults {
+                results,
+                hidden,
+                performance: None,
+                passed: visible_passed && hidden,
+            },
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    fn output(exit_code: i32, stdout: &str, files: &[(&str, Option<&str>)]) -> ShellOutput {
+        ShellOutput {
+            exit_code,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+            files: files
+                .iter()
+                .map(|(path, content)| (path.to_string(), content.map(str::to_string)))
+                .collect(),
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let test: ShellTest = toml::from_str(
+            r#"
+            stdout = "done"
+            [expect]
+            "out/a.txt" = "hi"
+            "out" = true
+            "a.txt" = false
+            "#,
+        )
+        .unwrap();
+
+        let good = [("out/", None), ("out/a.txt", Some("hi"))];
+        assert_eq!(test.check(&output(0, "done\n", &good)), None);
+        assert!(test.check(&output(1, "done\n", &good)).is_some());
+        assert!(test.check(&output(0, "nope", &good)).is_some());
+        assert!(test
+            .check(&output(
+                0,
+                "done",
+                &[("out/", None), ("out/a.txt", Some("ho"))]
+            ))
+            .is_some());
+        assert!(test
+            .check(&output(0, "done", &[("a.txt", Some("hi")), ("out/", None)]))
+            .is_some());
+        assert_eq!(
+            test.check(&ShellOutput {
+                timed_out: true,
+                ..output(0, "done\n", &good)
+            }),
+            Some("Timed out after 5 seconds".to_string())
+        );
+    }
+
+    #[test]
+    fn test_shell() {
+        let config = config_and_set_path().unwrap();
+        let shell: ShellConfig = toml::from_str(
+            r#"
+            [[tests]]
+            args = ["a.txt"]
+            stdin = "x"
+            files = { "a.txt" = "hi" }
+            stdout = "moved x"
+            expect = { "out/a.txt" = "hi", "a.txt" = false }
+
+            [[tests]]
+            args = ["missing.txt"]
+            exit_code = 1
+            hidden = true
+            "#,
+        )
+        .unwrap();
+        let slow: ShellConfig = toml::from_str(
+            r#"
+            [[tests]]
+            stdout = "done"
+            timeout = 0.5
+            "#,
+        )
+        .unwrap();
+        let script = "set -e\nmkdir -p out\nmv \"$1\" out/\necho moved $(cat)\n";
+
+        let results = shell.run_tests(&config, script, &HashMap::new()).unwrap();
+        assert!(results["script"].passed);
+        assert_eq!(results["script"].results.len(), 1);
+
+        let results = shell
+            .run_tests(&config, "echo moved", &HashMap::new())
+            .unwrap();
+        assert!(!results["script"].passed);
+        assert!(!results["script"].hidden);
+
+        // background jobs are killed too, so they can't hold the test up
+        let outputs = slow
+            .run(
+                &config,
+                "sleep 60 &\nsleep 60\necho done\n",
+                &HashMap::new(),
+            )
+            .unwrap();
+        assert!(outputs[0].timed_out);
+        assert!(slow.tests[0].check(&outputs[0]).is_some());
+    }
+}
@@ -0,0 +1,453 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use serde::Serialize;
+
+use crate::lang::Language;
+
+/// How many tokens each fingerprint covers, so shorter matches aren't found
+pub const K: usize = 5;
+/// How many fingerprints in a row winnowing keeps one of. Matches of at least
+/// `K + W - 1` tokens are always found.
+pub const W: usize = 4;
+
+/// Words that are kept as they are instead of being treated as identifiers,
+/// from every language
+const KEYWORDS: &[&str] = &[
+    "and", "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "def",
+    "default", "del", "do", "elif", "else", "enum", "except", "extends", "false", "False",
+    "finally", "fn", "for", "from", "function", "if", "impl", "import", "in", "lambda", "let",
+    "loop", "match", "mut", "new", "None", "not", "null", "or", "pass", "private", "public",
+    "raise", "return", "self", "static", "struct", "switch", "this", "throw", "true", "True",
+    "try", "var", "void", "while", "with", "yield",
+];
+
+/// A token of a submission, normalized so renaming things doesn't change it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// `v` for every identifier, `s` for every string or character and `l` for
+    /// every lifetime
+    pub text: String,
+    /// The line the token starts on, from 1
+    pub line: u32,
+}
+
+/// Split `code` into tokens, leaving out whitespace and comments. `comment`
+/// starts a line comment, with `//` languages also having block comments.
+pub fn tokenize(code: &str, comment: &str) -> Vec<Token> {
+    let chars = code.chars().collect::<Vec<_>>();
+    let comment = comment.chars().collect::<Vec<_>>();
+    let starts_with = |i: usize, s: &[char]| chars[i..].starts_with(s);
+
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start_line = line;
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if !comment.is_empty() && starts_with(i, &comment) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if comment == ['/', '/'] && starts_with(i, &['/', '*']) {
+            i += 2;
+            while i < chars.len() && !starts_with(i, &['*', '/']) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+        } else if c == '\'' && is_lifetime(&chars[i..]) {
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                text: "l".to_string(),
+                line: start_line,
+            });
+        } else if matches!(c, '"' | '\'' | '`') {
+            // python's triple quoted strings can span lines, like backticks
+            let triple = [c; 3];
+            let (end, multiline) = match starts_with(i, &triple) {
+                true => (&triple[..], true),
+                false => (&triple[..1], c == '`'),
+            };
+            i += end.len();
+            while i < chars.len() && !starts_with(i, end) && (multiline || chars[i] != '\n') {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            if i < chars.len() && starts_with(i, end) {
+                i += end.len();
+            }
+            tokens.push(Token {
+                text: "s".to_string(),
+                line: start_line,
+            });
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '.' | '_')) {
+                i += 1;
+            }
+            tokens.push(Token {
+                text: chars[start..i].iter().collect(),
+                line: start_line,
+            });
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            tokens.push(Token {
+                text: match KEYWORDS.contains(&word.as_str()) {
+                    true => word,
+                    false => "v".to_string(),
+                },
+                line: start_line,
+            });
+        } else {
+            i += 1;
+            tokens.push(Token {
+                text: c.to_string(),
+                line: start_line,
+            });
+        }
+
+        line += chars[start..i.min(chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count() as u32;
+    }
+
+    tokens
+}
+
+/// Whether `rest`, which starts with `'`, starts with a lifetime like `'a`
+/// rather than a string or character. A lifetime's identifier isn't followed by
+/// a closing quote, and the next quote on the line, if there is one, starts
+/// another lifetime or a character.
+fn is_lifetime(rest: &[char]) -> bool {
+    let ident = |c: &char| c.is_alphanumeric() || *c == '_';
+    if !rest.get(1).is_some_and(|c| c.is_alphabetic() || *c == '_') {
+        return false;
+    }
+    let end = 1 + rest[1..].iter().take_while(|c| ident(c)).count();
+    let line = &rest[end..];
+    let line = &line[..line.iter().position(|&c| c == '\n').unwrap_or(line.len())];
+    match line.iter().position(|&c| c == '\'') {
+        None => true,
+        Some(0) => false,
+        Some(quote) => line.get(quote + 1).is_some_and(ident),
+    }
+}
+
+/// A hash of `K` tokens in a row, picked by winnowing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub hash: u64,
+    /// The index of the first token
+    pub token: usize,
+}
+
+/// Hash every `K` tokens in a row
+fn hash_grams(tokens: &[Token]) -> Vec<u64> {
+    tokens
+        .windows(K)
+        .map(|gram| {
+            let mut hasher = DefaultHasher::new();
+            for token in gram {
+                token.text.hash(&mut hasher);
+            }
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Hash every `K` tokens in a row, keeping the smallest hash out of every `W`
+/// in a row
+pub fn winnow(tokens: &[Token]) -> Vec<Fingerprint> {
+    let hashes = hash_grams(tokens);
+    let mut fingerprints = Vec::<Fingerprint>::new();
+    for (start, window) in hashes.windows(W.min(hashes.len()).max(1)).enumerate() {
+        // the rightmost smallest hash, so runs of the same hash keep one
+        let (i, &hash) = window
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by_key(|(_, hash)| **hash)
+            .unwrap();
+        let token = start + i;
+        if fingerprints.last().is_none_or(|f| f.token != token) {
+            fingerprints.push(Fingerprint { hash, token });
+        }
+    }
+    fingerprints
+}
+
+/// A submission ready to be compared
+#[derive(Debug, Clone)]
+pub struct Document {
+    tokens: Vec<Token>,
+    fingerprints: Vec<Fingerprint>,
+}
+
+/// Split `code` into tokens with the comments of `lang`
+fn tokenize_lang(lang: &Language, code: &str) -> Vec<Token> {
+    // the delimiter is a comment, like `# ---`
+    let comment = lang.config().delimiter.split_whitespace().next();
+    tokenize(code, comment.unwrap_or_default())
+}
+
+impl Document {
+    pub fn new(lang: &Language, code: &str) -> Self {
+        let tokens = tokenize_lang(lang, code);
+        let fingerprints = winnow(&tokens);
+        Self {
+            tokens,
+            fingerprints,
+        }
+    }
+
+    fn hashes(&self) -> HashSet<u64> {
+        self.fingerprints.iter().map(|f| f.hash).collect()
+    }
+
+    /// The first and last line of the tokens a fingerprint covers
+    fn lines(&self, f: &Fingerprint) -> [u32; 2] {
+        [self.tokens[f.token].line, self.tokens[f.token + K - 1].line]
+    }
+}
+
+/// Lines of two submissions that have the same tokens
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct Match {
+    /// The first and last line in the first submission
+    pub a: [u32; 2],
+    /// The first and last line in the second submission
+    pub b: [u32; 2],
+}
+
+/// How alike two submissions are
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Similarity {
+    /// How many fingerprints the two have in common, out of how many either
+    /// has, from 0 to 1
+    pub score: f64,
+    /// Merged where they overlap, in order of the first submission
+    pub matches: Vec<Match>,
+}
+
+/// Compare two submissions, leaving out fingerprints in `ignore`
+pub fn compare(a: &Document, b: &Document, ignore: &HashSet<u64>) -> Similarity {
+    let (hashes_a, hashes_b) = (&a.hashes() - ignore, &b.hashes() - ignore);
+    let common = hashes_a.intersection(&hashes_b).count();
+    let total = hashes_a.len() + hashes_b.len() - common;
+    let score = match total {
+        0 => 0.0,
+        _ => common as f64 / total as f64,
+    };
+
+    let mut in_b = HashMap::<u64, Vec<&Fingerprint>>::new();
+    for f in b.fingerprints.iter().filter(|f| hashes_a.contains(&f.hash)) {
+        in_b.entry(f.hash).or_default().push(f);
+    }
+    let mut found = a
+        .fingerprints
+        .iter()
+        .flat_map(|fa| {
+            let matching = in_b.get(&fa.hash).map(|f| f.as_slice()).unwrap_or_default();
+            matching.iter().map(|fb| Match {
+                a: a.lines(fa),
+                b: b.lines(fb),
+            })
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|m| (m.a, m.b));
+
+    let touches = |x: [u32; 2], y: [u32; 2]| y[0] <= x[1] + 1 && x[0] <= y[1] + 1;
+    let mut matches = Vec::<Match>::new();
+    for m in found {
+        match matches
+            .iter_mut()
+            .rev()
+            .find(|prev| touches(prev.a, m.a) && touches(prev.b, m.b))
+        {
+            Some(prev) => {
+                prev.a = [prev.a[0].min(m.a[0]), prev.a[1].max(m.a[1])];
+                prev.b = [prev.b[0].min(m.b[0]), prev.b[1].max(m.b[1])];
+            }
+            None => matches.push(m),
+        }
+    }
+
+    Similarity { score, matches }
+}
+
+/// Compare every two of `submissions`, returning the indices of each pair with
+/// anything in common, most similar first. Anything also in `template` is left
+/// out, so starter code doesn't count.
+pub fn compare_all(
+    lang: &Language,
+    template: &str,
+    submissions: &[&str],
+) -> Vec<(usize, usize, Similarity)> {
+    // every hash rather than just the fingerprints, so none of it gets through
+    let ignore = hash_grams(&tokenize_lang(lang, template))
+        .into_iter()
+        .collect();
+    let documents = submissions
+        .iter()
+        .map(|code| Document::new(lang, code))
+        .collect::<Vec<_>>();
+
+    let mut pairs = Vec::new();
+    for (i, a) in documents.iter().enumerate() {
+        for (j, b) in documents.iter().enumerate().skip(i + 1) {
+            let similarity = compare(a, b, &ignore);
+            if similarity.score > 0.0 {
+                pairs.push((i, j, similarity));
+            }
+        }
+    }
+    pairs.sort_by(|(_, _, a), (_, _, b)| b.score.total_cmp(&a.score));
+    pairs
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    fn texts(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize(
+            "def total(xs):  # sum them\n    return sum(xs) + 1.5 - \"a # b\"\n",
+            "#",
+        );
+        assert_eq!(
+            texts(&tokens),
+            ["def", "v", "(", "v", ")", ":", "return", "v", "(", "v", ")", "+", "1.5", "-", "s"]
+        );
+        assert_eq!(tokens[6].line, 2);
+
+        let tokens = tokenize("int x = 1; /* a\nb */ // c\nx++;", "//");
+        assert_eq!(
+            texts(&tokens),
+            ["v", "v", "=", "1", ";", "v", "+", "+", ";"]
+        );
+        assert_eq!(tokens.last().unwrap().line, 3);
+
+        let tokens = tokenize("x = '''a\n'b'\n'''\ny", "#");
+        assert_eq!(texts(&tokens), ["v", "=", "s", "v"]);
+        assert_eq!(tokens[3].line, 4);
+
+        // characters are closed, unlike lifetimes
+        let tokens = tokenize(
+            "fn f<'a, 'b>(x: &'a str) -> char {\n    if x == \"it's\" { '\\n' } else { 'x' }\n}",
+            "//",
+        );
+        assert_eq!(
+            texts(&tokens),
+            [
+                "fn", "v", "<", "l", ",", "l", ">", "(", "v", ":", "&", "l", "v", ")", "-", ">",
+                "v", "{", "if", "v", "=", "=", "s", "{", "s", "}", "else", "{", "s", "}", "}"
+            ]
+        );
+        assert_eq!(tokens.last().unwrap().line, 3);
+
+        // python strings starting with a word are still strings
+        let tokens = tokenize("x = 'hello world' + 'a b'\ny", "#");
+        assert_eq!(texts(&tokens), ["v", "=", "s", "+", "s", "v"]);
+    }
+
+    #[test]
+    fn test_winnow() {
+        let tokens = tokenize("a b c d e f g h i j", "#");
+        assert!(winnow(&tokens[..K - 1]).is_empty());
+
+        // every fingerprint has the same hash, so each window keeps its last
+        let fingerprints = winnow(&tokens);
+        let last = tokens.len() - K;
+        assert_eq!(
+            fingerprints.iter().map(|f| f.token).collect::<Vec<_>>(),
+            (W - 1..=last).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        config_and_set_path().unwrap();
+        let lang: Language = "python".parse().unwrap();
+        let template = "def fizzbuzz(n):\n    pass\n";
+        let original = "\
+def fizzbuzz(n):
+    out = []
+    for i in range(1, n + 1):
+        if i % 15 == 0:
+            out.append('FizzBuzz')
+        elif i % 3 == 0:
+            out.append('Fizz')
+        elif i % 5 == 0:
+            out.append('Buzz')
+        else:
+            out.append(str(i))
+    return out
+";
+        let renamed = "\
+# my own work
+def fizzbuzz(count):
+    result = []
+
+
+    for x in range(1, count + 1):
+        if x % 15 == 0:
+            result.append(\"FizzBuzz\")
+        elif x % 3 == 0:
+            result.append(\"Fizz\")
+        elif x % 5 == 0:
+            result.append(\"Buzz\")
+        else:
+            result.append(str(x))
+    return result
+";
+        let different = "\
+def fizzbuzz(n):
+    return [
+        'Fizz' * (i % 3 == 0) + 'Buzz' * (i % 5 == 0) or str(i)
+        for i in range(1, n + 1)
+    ]
+";
+
+        let pairs = compare_all(&lang, template, &[original, renamed, different]);
+        let (i, j, similarity) = &pairs[0];
+        assert_eq!((*i, *j), (0, 1));
+        assert_eq!(similarity.score, 1.0);
+        // winnowing doesn't always keep the last fingerprint, so the last line
+        // can be left out
+        assert_eq!(
+            similarity.matches,
+            [Match {
+                a: [1, 11],
+                b: [2, 14]
+            }]
+        );
+        for (_, _, similarity) in &pairs[1..] {
+            assert!(similarity.score < 0.3, "{similarity:?}");
+        }
+
+        // only the template in common
+        let pairs = compare_all(&lang, template, &[template, template]);
+        assert!(pairs.is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use amplitude_common::config::Config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    exercise::{TestResult, TestResults},
+    lang::Language,
+    runner::{run, RunOptions, RunOutput},
+};
+
+/// The language SQL exercises are run in
+pub const SQL_LANGUAGE: &str = "sqlite";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SqlConfig {
+    /// Whether the rows have to be in the same order as the reference query's
+    #[serde(default)]
+    pub ordered: bool,
+    /// Whether the columns have to be named the same as the reference query's
+    #[serde(default)]
+    pub column_names: bool,
+    /// The contents of `schema.sql`, which creates and fills the tables
+    #[serde(skip_deserializing)]
+    pub schema: String,
+    /// What the reference query returned
+    #[serde(skip)]
+    pub expected: Option<ResultSet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// What `languages/sqlite/run.py` prints with `--json`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum QueryOutput {
+    #[serde(rename = "result")]
+    Result(ResultSet),
+    #[serde(rename = "error")]
+    Error { traceback: String },
+}
+
+pub fn sql_language() -> anyhow::Result<Language> {
+    SQL_LANGUAGE
+        .parse()
+        .with_context(|| format!("Language `{SQL_LANGUAGE}` not found in `languages.toml`"))
+}
+
+/// Run `query` against a fresh database made by `schema`, returning either
+/// the result of its last statement or the error it ran into
+pub fn run_query(
+    cfg: &Config,
+    schema: &str,
+    query: &str,
+    fixtures: &HashMap<String, Vec<u8>>,
+) -> anyhow::Result<Result<ResultSet, String>> {
+    let lang = sql_language()?;
+    let RunOutput {
+        stdout,
+        stderr,
+        truncated,
+        ..
+    } = run(
+        lang.config(),
+        &cfg.docker,
+        query,
+        fixtures
+            .iter()
+            .map(|(path, content)| (path.clone(), &content[..]))
+            .chain([("schema.sql".to_string(), schema.as_bytes())])
+            .collect(),
+        &RunOptions {
+            args: "--json",
+            ..Default::default()
+        },
+    )
+    .context("While running query")?;
+    anyhow::ensure!(
+        !truncated,
+        "Output exceeded the limit of {} bytes",
+        cfg.docker.output_limit
+    );
+
+    let output: QueryOutput = serde_json::from_str(&stdout).with_context(|| {
+        format!("While parsing query output\nstdout:\n{stdout}\n\nstderr:\n{stderr}")
+    })?;
+    Ok(match output {
+        QueryOutput::Result(result) => Ok(result),
+        QueryOutput::Error { traceback } => Err(traceback),
+    })
+}
+
+impl SqlConfig {
+    /// Whether `actual` is close enough to `expected`
+    pub fn matches(&self, expected: &ResultSet, actual: &ResultSet) -> bool {
+        if expected.columns.len() != actual.columns.len() {
+            return false;
+        }
+        if self.column_names
+            && !expected
+                .columns
+                .iter()
+                .zip(&actual.columns)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+        {
+            return false;
+        }
+        if self.ordered {
+            return expected.rows == actual.rows;
+        }
+
+        let sorted = |rows: &[Vec<Value>]| {
+            let mut rows = rows
+                .iter()
+                .map(|row| json!(row).to_string())
+                .collect::<Vec<_>>();
+            rows.sort();
+            rows
+        };
+        sorted(&expected.rows) == sorted(&actual.rows)
+    }
+
+    /// Run the student's `query` and compare it against the reference query
+    pub fn run_tests(
+        &self,
+        cfg: &Config,
+        query: &str,
+        fixtures: &HashMap<String, Vec<u8>>,
+    ) -> anyhow::Result<HashMap<String, TestResults>> {
+        let expected = self
+            .expected
+            .as_ref()
+            .context("Reference query has not been run")?;
+        let result = match run_query(cfg, &self.schema, query, fixtures)? {
+            Ok(actual) if self.matches(expected, &actual) => TestResult::Correct {
+                stdout: String::new(),
+            },
+            Ok(actual) => TestResult::Incorrect {
+                stdout: String::new(),
+                output: json!(actual),
+            },
+            Err(traceback) => TestResult::Error {
+                traceback,
+                stdout: String::new(),
+            },
+        };
+        let passed = matches!(result, TestResult::Correct { .. });
+
+        Ok(HashMap::from_iter([(
+            "query".to_string(),
+            TestResults {
+                results: vec![result],
+                // the one query is visible, so there are no hidden ones to fail
+                hidden: true,
+                performance: None,
+                passed,
+            },
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplitude_common::config_and_set_path;
+
+    use super::*;
+
+    fn result(columns: &[&str], rows: Value) -> ResultSet {
+        ResultSet {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: serde_json::from_value(rows).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let expected = result(&["a", "b"], json!([[1, "x"], [2, "y"]]));
+        let swapped = result(&["a", "b"], json!([[2, "y"], [1, "x"]]));
+        let renamed = result(&["A", "c"], json!([[1, "x"], [2, "y"]]));
+        let missing = result(&["a", "b"], json!([[1, "x"]]));
+
+        let cfg = SqlConfig::default();
+        assert!(cfg.matches(&expected, &swapped));
+        assert!(cfg.matches(&expected, &renamed));
+        assert!(!cfg.matches(&expected, &missing));
+        assert!(!cfg.matches(&expected, &result(&["a"], json!([[1], [2]]))));
+
+        let cfg = SqlConfig {
+            ordered: true,
+            column_names: true,
+            ..Default::default()
+        };
+        assert!(!cfg.matches(&expected, &swapped));
+        assert!(!cfg.matches(&expected, &renamed));
+        assert!(cfg.matches(&expected, &result(&["A", "B"], json!([[1, "x"], [2, "y"]]))));
+    }
+
+    #[test]
+    fn test_sql() {
+        let config = config_and_set_path().unwrap();
+        let schema =
+            "CREATE TABLE t (a INTEGER, b TEXT);\nINSERT INTO t VALUES (1, 'x'), (2, 'y');";
+        let query = |q| run_query(&config, schema, q, &HashMap::new()).unwrap();
+
+        assert_eq!(
+            query("SELECT a, b FROM t ORDER BY a DESC;"),
+            Ok(result(&["a", "b"], json!([[2, "y"], [1, "x"]])))
+        );
+        assert!(query("SELEC nothing;").is_err());
+
+        let sql = SqlConfig {
+            expected: Some(result(&["b"], json!([["x"], ["y"]]))),
+            schema: schema.to_string(),
+            ..Default::default()
+        };
+        let results = sql
+            .run_tests(&config, "SELECT b FROM t;", &HashMap::new())
+            .unwrap();
+        assert!(results["query"].passed);
+
+        let results = sql
+            .run_tests(&config, "SELECT a FROM t;", &HashMap::new())
+            .unwrap();
+        assert!(!results["query"].passed);
+        assert!(results["query"].hidden);
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::exercise::TestOutput;
+
+/// The most line events recorded in a trace
+pub const MAX_TRACE_EVENTS: usize = 1000;
+/// How many characters of a variable that can't be turned into json are kept
+pub const MAX_TRACE_REPR: usize = 200;
+
+/// One line being run in the student's code
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TraceEvent {
+    /// The line about to be run
+    pub line: u32,
+    /// The functions being run, outermost first
+    pub stack: Vec<String>,
+    /// The variables in the innermost function, as json if they can be
+    pub locals: Map<String, Value>,
+    /// Everything printed so far
+    pub stdout: String,
+}
+
+/// Every line run while calling a function with one test case
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+    /// Whether recording stopped early because the trace got too big
+    pub truncated: bool,
+}
+
+/// The result of tracing a test case
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TraceResult {
+    pub trace: Trace,
+    pub output: TestOutput,
+    /// What the test case expects the function to return
+    pub expected: Value,
+}
+
+/// What the runner template is rendered with in trace mode, keeping the trace
+/// to at most `max_bytes` of json
+pub fn trace_options(max_bytes: usize) -> Value {
+    json!({
+        "max_events": MAX_TRACE_EVENTS,
+        "max_repr": MAX_TRACE_REPR,
+        "max_bytes": max_bytes,
+    })
+}
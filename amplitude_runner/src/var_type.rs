@@ -1,6 +1,8 @@
 use std::{collections::HashMap, fmt};
 
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
@@ -15,6 +17,50 @@ pub enum VariableType {
     Tuple(Vec<VariableType>),
 }
 
+impl VariableType {
+    /// Check that `value` is of this type
+    pub fn check(&self, value: &Value) -> anyhow::Result<()> {
+        match (self, value) {
+            (VariableType::Int, Value::Number(n)) if n.is_i64() || n.is_u64() => Ok(()),
+            (VariableType::Float, Value::Number(_)) => Ok(()),
+            (VariableType::String, Value::String(_)) => Ok(()),
+            (VariableType::Boolean, Value::Bool(_)) => Ok(()),
+            (VariableType::Array(ty), Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    ty.check(item).with_context(|| format!("In item {i}"))?;
+                }
+                Ok(())
+            }
+            (VariableType::Struct(fields), Value::Object(map)) => {
+                for (name, ty) in fields {
+                    let field = map
+                        .get(name)
+                        .with_context(|| format!("Missing field `{name}`"))?;
+                    ty.check(field)
+                        .with_context(|| format!("In field `{name}`"))?;
+                }
+                if let Some(name) = map.keys().find(|k| !fields.contains_key(*k)) {
+                    anyhow::bail!("Unexpected field `{name}`");
+                }
+                Ok(())
+            }
+            (VariableType::Tuple(types), Value::Array(items)) => {
+                anyhow::ensure!(
+                    types.len() == items.len(),
+                    "Expected a tuple of {} items, found {}",
+                    types.len(),
+                    items.len()
+                );
+                for (i, (ty, item)) in types.iter().zip(items).enumerate() {
+                    ty.check(item).with_context(|| format!("In item {i}"))?;
+                }
+                Ok(())
+            }
+            _ => anyhow::bail!("Expected `{self}`, found `{value}`"),
+        }
+    }
+}
+
 impl From<VariableType> for String {
     fn from(value: VariableType) -> Self {
         String::from(&value)
@@ -140,6 +186,8 @@ impl TryFrom<String> for VariableType {
 
 #[cfg(test)]
 mod test {
+    use serde_json::json;
+
     use super::*;
 
     #[test]
@@ -202,4 +250,27 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_check() {
+        let ty = |s| VariableType::try_from(s).unwrap();
+        let ok = |t, v: Value| assert!(ty(t).check(&v).is_ok(), "{t} {v}");
+        let err = |t, v: Value| assert!(ty(t).check(&v).is_err(), "{t} {v}");
+
+        ok("int", json!(1));
+        err("int", json!(1.5));
+        ok("float", json!(1));
+        ok("float", json!(1.5));
+        err("string", json!(1));
+        ok("bool", json!(true));
+        ok("int[]", json!([]));
+        ok("int[][]", json!([[1], [2, 3]]));
+        err("int[]", json!([1, "2"]));
+        ok("{ a: int, b: string }", json!({ "a": 1, "b": "b" }));
+        err("{ a: int }", json!({ "a": 1, "b": "b" }));
+        err("{ a: int, b: int }", json!({ "a": 1 }));
+        ok("(int, string)", json!([1, "a"]));
+        err("(int, string)", json!([1]));
+        err("(int, string)", json!(["a", 1]));
+    }
 }
@@ -0,0 +1,71 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::exercise::{Exercise, TestCase};
+
+/// A student's own visible test cases for an exercise, by function
+pub type Variant = HashMap<String, Vec<TestCase>>;
+
+/// The seed `user`'s test cases for `func` are generated from, so they're the
+/// same every time
+pub fn variant_seed(exercise: &str, user: &str, func: &str) -> u64 {
+    let hash = Sha256::new()
+        .chain_update(exercise)
+        .chain_update([0])
+        .chain_update(user)
+        .chain_update([0])
+        .chain_update(func)
+        .finalize();
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+/// Changes whenever anything variants of `exercise` are generated from does,
+/// so variants made before the exercise was changed can be told apart
+pub fn fingerprint(exercise: &Exercise) -> String {
+    let functions = exercise
+        .config
+        .functions
+        .iter()
+        .filter(|(_, func)| func.variants)
+        .map(|(name, func)| {
+            (
+                name,
+                json!({
+                    "inputs": func.inputs,
+                    "output": func.output,
+                    "constraints": func.constraints,
+                    "visible_cases": func.visible_cases,
+                }),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let solution = exercise.solution.as_ref().map(|s| {
+        json!([
+            s.lang.name(),
+            s.code,
+            s.files.iter().collect::<BTreeMap<_, _>>()
+        ])
+    });
+
+    let hash = Sha256::new()
+        .chain_update(json!([functions, solution]).to_string())
+        .finalize();
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_variant_seed() {
+        let seed = variant_seed("a/b", "user", "f");
+        assert_eq!(seed, variant_seed("a/b", "user", "f"));
+        assert_ne!(seed, variant_seed("a/b", "other", "f"));
+        assert_ne!(seed, variant_seed("a/b", "user", "g"));
+        // the separators keep the parts from running together
+        assert_ne!(variant_seed("a", "bc", "f"), variant_seed("ab", "c", "f"));
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use amplitude_common::config::DockerConfig;
+use anyhow::Context;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::runner::{run_local, RunOptions, RunOutput};
+
+/// Code sent to a worker's `POST /run`, which responds with its `RunOutput`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// The name of the language in `languages.toml`
+    pub lang: String,
+    pub src: String,
+    /// The other files, base64 encoded
+    #[serde(default)]
+    pub files: HashMap<String, String>,
+    #[serde(default)]
+    pub args: String,
+    #[serde(default)]
+    pub stdin: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+impl Job {
+    pub fn new(
+        lang: &str,
+        src: &str,
+        other_files: &HashMap<String, &[u8]>,
+        opts: &RunOptions,
+    ) -> Self {
+        Self {
+            lang: lang.to_string(),
+            src: src.to_string(),
+            files: other_files
+                .iter()
+                .map(|(path, content)| (path.clone(), STANDARD.encode(content)))
+                .collect(),
+            args: opts.args.to_string(),
+            stdin: opts.stdin.map(|s| s.to_string()),
+            command: opts.command.map(|s| s.to_string()),
+        }
+    }
+
+    /// The other files, decoded
+    pub fn files(&self) -> anyhow::Result<HashMap<String, Vec<u8>>> {
+        self.files
+            .iter()
+            .map(|(path, content)| {
+                let content = STANDARD
+                    .decode(content)
+                    .with_context(|| format!("File `{path}` isn't valid base64"))?;
+                Ok((path.clone(), content))
+            })
+            .collect()
+    }
+
+    /// Run the job with docker on this machine
+    pub fn run(&self, cfg: &DockerConfig) -> anyhow::Result<RunOutput> {
+        let lang = cfg
+            .language_config
+            .get(&self.lang)
+            .with_context(|| format!("Language `{}` not found", self.lang))?;
+        let files = self.files()?;
+        run_local(
+            lang,
+            cfg,
+            &self.src,
+            files
+                .iter()
+                .map(|(path, content)| (path.clone(), content.as_slice()))
+                .collect(),
+            &RunOptions {
+                args: &self.args,
+                stdin: self.stdin.as_deref(),
+                command: self.command.as_deref(),
+            },
+        )
+    }
+}
+
+/// What a worker's `GET /health` responds with
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Health {
+    /// The languages that passed the health check when the worker started
+    pub languages: Vec<String>,
+}
+
+/// What a worker's `GET /capacity` responds with
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Capacity {
+    /// How many jobs can run at once
+    pub slots: usize,
+    /// How many jobs are running
+    pub busy: usize,
+}
+
+/// Limits how many jobs a worker runs at once
+pub struct Slots {
+    total: usize,
+    busy: AtomicUsize,
+}
+
+/// A job's place in `Slots`, given back when dropped
+pub struct Slot<'a>(&'a AtomicUsize);
+
+impl Slots {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            busy: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take a slot if one is free
+    pub fn take(&self) -> Option<Slot<'_>> {
+        self.busy
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |busy| {
+                (busy < self.total).then_some(busy + 1)
+            })
+            .ok()?;
+        Some(Slot(&self.busy))
+    }
+
+    pub fn capacity(&self) -> Capacity {
+        Capacity {
+            slots: self.total,
+            busy: self.busy.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_job() {
+        let files = HashMap::from_iter([
+            ("data.txt".to_string(), "1 2 3".as_bytes()),
+            ("data.bin".to_string(), [0, 159, 255].as_slice()),
+        ]);
+        let opts = RunOptions {
+            stdin: Some("input"),
+            ..Default::default()
+        };
+        let job = Job::new("python", "print(input())", &files, &opts);
+
+        let job: Job = serde_json::from_str(&serde_json::to_string(&job).unwrap()).unwrap();
+        assert_eq!(job.lang, "python");
+        assert_eq!(job.stdin.as_deref(), Some("input"));
+        assert_eq!(job.command, None);
+        let decoded = job.files().unwrap();
+        assert_eq!(decoded.len(), 2);
+        for (path, content) in files {
+            assert_eq!(decoded[&path], content);
+        }
+
+        let job = Job {
+            files: HashMap::from_iter([("a".to_string(), "not base64!".to_string())]),
+            ..job
+        };
+        assert!(job.files().is_err());
+    }
+
+    #[test]
+    fn test_slots() {
+        let slots = Slots::new(2);
+        let a = slots.take().unwrap();
+        let b = slots.take().unwrap();
+        assert!(slots.take().is_none());
+        assert_eq!(slots.capacity(), Capacity { slots: 2, busy: 2 });
+
+        drop(a);
+        assert_eq!(slots.capacity(), Capacity { slots: 2, busy: 1 });
+        let _c = slots.take().unwrap();
+        assert!(slots.take().is_none());
+        drop(b);
+        assert_eq!(slots.capacity().busy, 1);
+    }
+}
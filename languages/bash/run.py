@@ -0,0 +1,78 @@
+# Runs `main.sh` with the given arguments. With `--tests <file>`, runs it once
+# for every test in the file instead, each in a fresh directory, and prints
+# what it did as json.
+import json
+import os
+import signal
+import subprocess
+import sys
+import tempfile
+
+SCRIPT = os.path.abspath("main.sh")
+
+
+def setup(root, files):
+    for path, content in files.items():
+        path = os.path.join(root, path)
+        if path.endswith("/"):
+            os.makedirs(path, exist_ok=True)
+            continue
+        os.makedirs(os.path.dirname(path), exist_ok=True)
+        with open(path, "w") as f:
+            f.write(content)
+
+
+def snapshot(root):
+    files = {}
+    for parent, dirs, names in os.walk(root):
+        for name in dirs + names:
+            path = os.path.join(parent, name)
+            rel = os.path.relpath(path, root)
+            if os.path.isdir(path) and not os.path.islink(path):
+                files[rel + "/"] = None
+                continue
+            try:
+                with open(path, "rb") as f:
+                    files[rel] = f.read().decode(errors="replace")
+            except OSError:
+                files[rel] = None
+    return files
+
+
+def run_test(test):
+    root = tempfile.mkdtemp(dir="/tmp")
+    setup(root, test.get("files", {}))
+    # in its own session, so everything it starts can be killed on a timeout
+    proc = subprocess.Popen(
+        ["bash", SCRIPT, *test.get("args", [])],
+        cwd=root,
+        stdin=subprocess.PIPE,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.PIPE,
+        text=True,
+        errors="replace",
+        start_new_session=True,
+    )
+    timed_out = False
+    try:
+        stdout, stderr = proc.communicate(test.get("stdin") or "", timeout=test["timeout"])
+    except subprocess.TimeoutExpired:
+        timed_out = True
+        os.killpg(proc.pid, signal.SIGKILL)
+        stdout, stderr = proc.communicate()
+    return {
+        "exit_code": proc.returncode,
+        "stdout": stdout,
+        "stderr": stderr,
+        "files": snapshot(root),
+        "timed_out": timed_out,
+    }
+
+
+args = sys.argv[1:]
+if args[:1] != ["--tests"]:
+    os.execvp("bash", ["bash", SCRIPT, *args])
+
+with open(args[1]) as f:
+    tests = json.load(f)
+print(json.dumps([run_test(test) for test in tests]))
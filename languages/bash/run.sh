@@ -0,0 +1,2 @@
+function urldecode() { : "${*//+/ }"; echo -e "${_//%/\\x}"; }
+timeout --signal=KILL ${TIMEOUT} python /opt/runner/run.py $(urldecode "${ARGS}")
\ No newline at end of file
@@ -1,2 +1,4 @@
 function urldecode() { : "${*//+/ }"; echo -e "${_//%/\\x}"; }
-timeout --signal=KILL ${TIMEOUT} gcc -o main src/main.c && timeout --signal=KILL ${TIMEOUT} ./main $(urldecode "${ARGS}")
\ No newline at end of file
+# the line after the compiler's output tells the runner the code didn't compile
+timeout --signal=KILL ${TIMEOUT} gcc -fdiagnostics-plain-output -o main src/main.c || { echo "::amplitude-compile-error::" >&2; exit 1; }
+timeout --signal=KILL ${TIMEOUT} ./main $(urldecode "${ARGS}")
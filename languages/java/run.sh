@@ -1,3 +1,4 @@
 function urldecode() { : "${*//+/ }"; echo -e "${_//%/\\x}"; }
-timeout --signal=KILL ${TIMEOUT} javac Main.java
-timeout --signal=KILL ${TIMEOUT} java -cp . Main $(urldecode "${ARGS}")
\ No newline at end of file
+# the line after the compiler's output tells the runner the code didn't compile
+timeout --signal=KILL ${TIMEOUT} javac Main.java || { echo "::amplitude-compile-error::" >&2; exit 1; }
+timeout --signal=KILL ${TIMEOUT} java -cp . Main $(urldecode "${ARGS}")
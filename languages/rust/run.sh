@@ -1,2 +1,12 @@
 function urldecode() { : "${*//+/ }"; echo -e "${_//%/\\x}"; }
-timeout --signal=KILL ${TIMEOUT} /usr/local/cargo/bin/cargo run -- $(urldecode "${ARGS}")
\ No newline at end of file
+# the root filesystem is read only, so cargo has to work inside the tmpfs
+export CARGO_HOME=/tmp/cargo
+cp /opt/runner/Cargo.toml .
+# the compiler's json messages are only shown if the build fails, followed by
+# a line that tells the runner the code didn't compile
+if ! timeout --signal=KILL ${TIMEOUT} /usr/local/cargo/bin/cargo build -q --message-format=json > /tmp/build.json; then
+    cat /tmp/build.json >&2
+    echo "::amplitude-compile-error::" >&2
+    exit 1
+fi
+timeout --signal=KILL ${TIMEOUT} /usr/local/cargo/bin/cargo run -q -- $(urldecode "${ARGS}")
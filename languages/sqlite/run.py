@@ -0,0 +1,54 @@
+# Runs `main.sql` against an in-memory database, after `schema.sql` if there is
+# one. With `--json`, prints the result of the last statement as json instead.
+import json
+import os
+import sqlite3
+import sys
+
+
+def statements(sql):
+    buf = ""
+    for c in sql:
+        buf += c
+        if c == ";" and sqlite3.complete_statement(buf):
+            yield buf
+            buf = ""
+    if buf.strip():
+        yield buf
+
+
+def value(v):
+    if isinstance(v, bytes):
+        return v.hex()
+    return v
+
+
+as_json = "--json" in sys.argv[1:]
+db = sqlite3.connect(":memory:")
+result = {"type": "result", "columns": [], "rows": []}
+
+try:
+    if os.path.exists("schema.sql"):
+        with open("schema.sql") as f:
+            db.executescript(f.read())
+    with open("main.sql") as f:
+        query = f.read()
+
+    for statement in statements(query):
+        cursor = db.execute(statement)
+        if cursor.description is None:
+            continue
+        columns = [d[0] for d in cursor.description]
+        rows = [[value(v) for v in row] for row in cursor.fetchall()]
+        result = {"type": "result", "columns": columns, "rows": rows}
+        if not as_json:
+            for row in rows:
+                print("|".join("" if v is None else str(v) for v in row))
+except sqlite3.Error as e:
+    if not as_json:
+        print(f"Error: {e}", file=sys.stderr)
+        sys.exit(1)
+    result = {"type": "error", "traceback": f"{type(e).__name__}: {e}"}
+
+if as_json:
+    print(json.dumps(result))
@@ -67,20 +67,103 @@ export type TestResult =
           type: "error";
           stdout: string;
           traceback: string;
+      }
+    | {
+          type: "diverged";
+          stdout: string;
+          call: number;
+          output: Object;
+      };
+
+export type TestOutput =
+    | {
+          type: "answer";
+          value: Object;
+          stdout: string;
+      }
+    | {
+          type: "exception";
+          traceback: string;
+          stdout: string;
       };
 
 export class TestResults {
     [key: string]: {
         results: TestResult[];
         hidden: boolean;
+        performance?: {
+            passed: boolean;
+            ratio: number;
+        };
         passed: boolean;
     };
 }
 
+export type Formatted =
+    | {
+          type: "code";
+          code: string;
+      }
+    | {
+          type: "error";
+          message: string;
+      };
+
+export class Diagnostic {
+    file: string | null;
+    line: number;
+    column: number | null;
+    severity: "error" | "warning" | "note";
+    message: string;
+}
+
+export class CompileError {
+    output: string;
+    diagnostics: Diagnostic[];
+}
+
+export class Evaluation {
+    results: TestResults;
+    coverage?: {
+        covered: number[];
+        uncovered: number[];
+    };
+    compile_error?: CompileError;
+    style: {
+        diagnostics: Diagnostic[];
+        score: number | null;
+    } | null;
+}
+
+export class TraceEvent {
+    line: number;
+    stack: string[];
+    locals: { [name: string]: Object };
+    stdout: string;
+}
+
+export class TraceResult {
+    trace: {
+        events: TraceEvent[];
+        truncated: boolean;
+    };
+    output: TestOutput;
+    expected: Object;
+}
+
+export class MutationResults {
+    correct_passes: boolean;
+    killed: number;
+    total: number;
+    survived: string[];
+    passed: boolean;
+}
+
 export class CategoryConfig {
     title: string;
     description: string;
     exercises: string[];
+    parsons: string[];
 }
 
 export type CategoryConfigs = { [key: string]: CategoryConfig };
@@ -132,12 +215,51 @@ export class ExerciseConfig {
             output: string;
             hidden_cases: number;
             visible_cases: number;
+            variants: boolean;
+            performance?: {
+                cases: number;
+                factor: number;
+            };
             tests: {
                 inputs: Object[];
                 output: Object;
             }[];
         };
     };
+    classes?: {
+        [key: string]: {
+            constructor: string[];
+            methods: { [key: string]: { inputs: string[]; output?: string } };
+            tests: {
+                constructor: Object[];
+                calls: { method: string; inputs: Object[]; output: Object }[];
+            }[];
+        };
+    };
+    sql?: {
+        ordered: boolean;
+        column_names: boolean;
+        schema: string;
+    };
+    shell?: {
+        tests: {
+            args: string[];
+            stdin?: string;
+            files: { [path: string]: string };
+            exit_code: number;
+            stdout?: string;
+            expect: { [path: string]: string | boolean };
+            timeout: number;
+        }[];
+    };
+    mutation?: {
+        module: string;
+    };
+    coverage: boolean;
+    style?: {
+        points: number | null;
+        penalty: number;
+    };
 }
 
 export class ExerciseData {
@@ -145,6 +267,9 @@ export class ExerciseData {
     lang_info: {
         [key: string]: {
             code: string;
+            files: { [path: string]: string };
+            available: boolean;
+            regions: { code: string; editable: boolean }[];
         };
     };
     type?: "exercise";
@@ -177,6 +302,28 @@ export const getExercise = async (id: string): Promise<ExerciseData> => {
     });
 };
 
+export class ParsonsData {
+    title: string;
+    instructions: string;
+    lang: string;
+    grading: "order" | "run";
+    lines: { id: string; code: string }[];
+}
+
+export class ParsonsResult {
+    passed: boolean;
+    results?: TestResults;
+}
+
+export const getParsons = async (id: string): Promise<ParsonsData> => {
+    return await fetchApi("/api/parsons", {
+        method: "POST",
+        body: {
+            id: id,
+        },
+    });
+};
+
 export const getCategoryExercises = async (category: string): Promise<{ [key: string]: ExerciseConfig }> => {
     return await fetchApi("/api/exercise/category", {
         method: "POST",
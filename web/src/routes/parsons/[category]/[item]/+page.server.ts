@@ -0,0 +1,7 @@
+import { getParsons } from "$lib/fetch";
+import type { ParsonsData } from "$lib/fetch";
+
+export const load = async ({ params }): Promise<ParsonsData & { id: string }> => {
+    const id = `${params.category}/${params.item}`;
+    return { ...(await getParsons(id)), id };
+};